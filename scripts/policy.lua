@@ -0,0 +1,42 @@
+-- Пример политики обработки обратной связи.
+-- Подключается через Params::policy_script.
+
+-- Слова, при которых обратная связь передаётся менеджеру.
+local ESCALATE_WORDS = { "брак", "возврат", "подделка", "обман" }
+
+local function contains_any(text, words)
+	local lower = string.lower(text)
+	for _, w in ipairs(words) do
+		if string.find(lower, w, 1, true) then
+			return true
+		end
+	end
+	return false
+end
+
+function on_question(q, ctx)
+	if #q.text < 3 then
+		return { action = "skip", reason = "empty question" }
+	end
+	if contains_any(q.text, ESCALATE_WORDS) then
+		return { action = "escalate", reason = "sensitive question" }
+	end
+	return nil
+end
+
+function on_review(r, ctx)
+	if r.score <= 2 or contains_any(r.text, ESCALATE_WORDS) then
+		return { action = "escalate", reason = "negative review" }
+	end
+	if r.score == 5 and r.text == "" then
+		return { action = "rewrite", text = "Спасибо за высокую оценку! Рады, что товар вам понравился." }
+	end
+	return nil
+end
+
+function before_publish(answer)
+	if string.find(answer.text, "http", 1, true) then
+		return { action = "escalate", reason = "answer contains a link" }
+	end
+	return nil
+end
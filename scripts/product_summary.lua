@@ -0,0 +1,26 @@
+-- Формирует AI-сводки по всем товарам и сохраняет их в базу данных.
+-- Аргументы:
+--   template — шаблон промпта в каталоге шаблонов (по умолчанию product_summary.j2),
+--   model    — модель AI провайдера (по умолчанию модель окружения задачи).
+
+local template = args.template or "product_summary.j2"
+
+for n, product in ipairs(seller.products()) do
+	local ok, info = pcall(seller.product_info, product.id)
+
+	if not ok then
+		print("Ошибка формирования данных контекста товара " .. product.id .. ": " .. tostring(info))
+	else
+		local source = { content_hash = seller.content_hash(info), template = template, model = args.model or ai.model }
+		info.place = seller.place
+
+		local prompt = templates.render(template, { product = info })
+		local summary, model = ai.chat({ { role = "user", content = prompt } }, { model = args.model, task = "summary" })
+
+		db.set_summary(product.id, summary, source)
+
+		print(n .. ". " .. seller.shop .. "/" .. product.id .. " ai_summary (" .. model .. "):")
+		print(summary)
+		print("------------------------------------------------")
+	end
+end
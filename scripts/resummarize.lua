@@ -0,0 +1,37 @@
+-- Обновляет AI-сводки товаров, у которых с момента формирования сводки изменились
+-- данные товара, шаблон промпта или модель. Остальные сводки не формируются повторно.
+-- Аргументы те же, что у product_summary.lua:
+--   template — шаблон промпта в каталоге шаблонов (по умолчанию product_summary.j2),
+--   model    — модель AI провайдера (по умолчанию модель окружения задачи).
+
+local template = args.template or "product_summary.j2"
+local updated, fresh, failed = 0, 0, 0
+
+for _, product in ipairs(seller.products()) do
+	local ok, info = pcall(seller.product_info, product.id)
+
+	if not ok then
+		failed = failed + 1
+		print("Ошибка формирования данных контекста товара " .. product.id .. ": " .. tostring(info))
+	else
+		local source = { content_hash = seller.content_hash(info), template = template, model = args.model or ai.model }
+
+		if db.is_summary_fresh(product.id, source) then
+			fresh = fresh + 1
+		else
+			info.place = seller.place
+
+			local prompt = templates.render(template, { product = info })
+			local summary, model = ai.chat({ { role = "user", content = prompt } }, { model = args.model, task = "summary" })
+
+			db.set_summary(product.id, summary, source)
+			updated = updated + 1
+
+			print(seller.shop .. "/" .. product.id .. " ai_summary обновлена (" .. model .. "):")
+			print(summary)
+			print("------------------------------------------------")
+		end
+	end
+end
+
+print("Сводок обновлено: " .. updated .. ", без изменений: " .. fresh .. ", ошибок: " .. failed)
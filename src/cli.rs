@@ -0,0 +1,795 @@
+use crate::{
+    config::Config,
+    controller::{
+        FeedbackController, ProductContext,
+        dotlua::{JobEnv, LuaJob},
+        feedback::Params,
+    },
+    db,
+    error::{Error, Result},
+    genai::{self, ChatStreamEvent, ModelRouter},
+    sellerapi::{
+        OZON_PLACE_SYMBOL, UnansweredFilter, WB_PLACE_SYMBOL,
+        abcmodels::{DEFAULT_AUTHOR_NAME, NewFeedback, NewQuestion},
+    },
+    shop::{Shop, ShopRegistry},
+    webapp,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::{collections::HashMap, io::Write, process::ExitCode, sync::Arc, time::Duration};
+use tera::{Context, Tera};
+
+/// Код завершения: ошибка выполнения команды.
+pub const EXIT_FAILURE: u8 = 1;
+/// Код завершения: неверные аргументы командной строки (используется clap).
+pub const EXIT_USAGE: u8 = 2;
+/// Код завершения: ошибка конфигурации, шаблона или скрипта.
+pub const EXIT_CONFIG: u8 = 3;
+/// Код завершения: ошибка API маркетплейса или AI провайдера.
+pub const EXIT_API: u8 = 4;
+/// Код завершения: ошибка базы данных.
+pub const EXIT_DB: u8 = 5;
+
+/// Автоматизация ответов на отзывы и вопросы покупателей Ozon и Wildberries.
+#[derive(Debug, Parser)]
+#[command(name = "blueberry", version)]
+pub struct Cli {
+    /// Файл конфигурации (TOML или JSON).
+    #[arg(long, short, global = true, env = "BLUEBERRY_CONFIG")]
+    pub config: Option<String>,
+
+    /// Магазин из раздела `shops` конфигурации. Обязателен, если магазинов несколько.
+    #[arg(long, short, global = true, env = "BLUEBERRY_SHOP")]
+    pub shop: Option<String>,
+
+    /// Маркетплейс магазина по умолчанию. Переопределяет `place` из конфигурации.
+    #[arg(long, short, global = true, value_enum)]
+    pub place: Option<Place>,
+
+    /// Модель AI провайдера. Переопределяет `llm_config.model` из конфигурации.
+    #[arg(long, short, global = true, env = "BLUEBERRY_MODEL")]
+    pub model: Option<String>,
+
+    /// Путь к базе данных. Переопределяет `db.path` из конфигурации.
+    #[arg(long, global = true)]
+    pub db: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Place {
+    /// Ozon
+    Oz,
+    /// Wildberries
+    Wb,
+}
+
+impl Place {
+    pub fn str_symbol(&self) -> &'static str {
+        match self {
+            Self::Oz => OZON_PLACE_SYMBOL,
+            Self::Wb => WB_PLACE_SYMBOL,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Сформировать AI-сводки по всем товарам.
+    Summarize {
+        /// Lua скрипт задачи.
+        #[arg(long, default_value = "scripts/product_summary.lua")]
+        script: String,
+
+        /// Шаблон промпта в каталоге шаблонов.
+        #[arg(long, default_value = "product_summary.j2")]
+        template: String,
+    },
+
+    /// Обновить AI-сводки товаров, данные которых, шаблон или модель изменились.
+    Resummarize {
+        /// Lua скрипт задачи.
+        #[arg(long, default_value = "scripts/resummarize.lua")]
+        script: String,
+
+        /// Шаблон промпта в каталоге шаблонов.
+        #[arg(long, default_value = "product_summary.j2")]
+        template: String,
+    },
+
+    /// Запустить обработчик новых вопросов и отзывов
+    /// для выбранного магазина или для всех магазинов одновременно.
+    Observe(ObserveArgs),
+
+    /// Обработать вопросы и отзывы, уже ожидающие ответа на маркетплейсе.
+    Backlog(BacklogArgs),
+
+    /// Сгенерировать ответ на вопрос по товару без публикации.
+    Ask {
+        /// Идентификатор товара (SKU Ozon или nmID Wildberries).
+        #[arg(long)]
+        product: String,
+
+        /// Текст вопроса. Если не задан, вопросы читаются из stdin.
+        #[arg(long)]
+        question: Option<String>,
+
+        /// Шаблон промпта для ответа на вопрос. По умолчанию шаблон магазина.
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Выводить ответ по мере генерации.
+        #[arg(long)]
+        stream: bool,
+    },
+
+    /// Опубликовать ответ на вопрос или отзыв.
+    Answer {
+        #[arg(value_enum)]
+        kind: FeedbackKind,
+
+        /// Идентификатор вопроса или отзыва.
+        id: String,
+
+        /// Текст ответа.
+        text: String,
+
+        /// Идентификатор товара (обязателен для вопросов Ozon).
+        #[arg(long)]
+        product: Option<String>,
+    },
+
+    /// Запустить веб-интерфейс.
+    Serve {
+        /// Адрес для входящих соединений.
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+
+    /// Работа с шаблонами промптов.
+    #[command(subcommand)]
+    Templates(TemplatesCommand),
+
+    /// Работа с базой данных.
+    #[command(subcommand)]
+    Db(DbCommand),
+
+    /// Выполнить Lua скрипт задачи.
+    Run {
+        /// Путь к скрипту.
+        script: String,
+
+        /// Аргументы скрипта в виде `key=value`.
+        #[arg(long = "arg", value_parser = parse_key_value)]
+        args: Vec<(String, String)>,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct ObserveArgs {
+    /// Интервал опроса новых вопросов, секунды.
+    #[arg(long)]
+    pub question_interval: Option<u64>,
+
+    /// Интервал опроса новых отзывов, секунды.
+    #[arg(long)]
+    pub review_interval: Option<u64>,
+
+    /// Шаблон промпта для ответа на вопрос.
+    #[arg(long)]
+    pub question_template: Option<String>,
+
+    /// Шаблон промпта для ответа на отзыв.
+    #[arg(long)]
+    pub review_template: Option<String>,
+
+    /// Lua скрипт политики обработки.
+    #[arg(long)]
+    pub policy: Option<String>,
+
+    /// Публиковать ответы без одобрения.
+    #[arg(long)]
+    pub no_approval: bool,
+
+    /// При запуске обработать вопросы и отзывы, уже ожидающие ответа.
+    #[arg(long)]
+    pub backlog: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct BacklogArgs {
+    /// Только вопросы или только отзывы.
+    #[arg(long, value_enum)]
+    pub kind: Option<FeedbackKind>,
+
+    /// Начало периода публикации: `YYYY-MM-DD` или Unix timestamp.
+    #[arg(long, value_parser = parse_date_from)]
+    pub from: Option<u64>,
+
+    /// Конец периода публикации (включительно): `YYYY-MM-DD` или Unix timestamp.
+    #[arg(long, value_parser = parse_date_to)]
+    pub to: Option<u64>,
+
+    /// Идентификатор товара. Можно указать несколько раз.
+    #[arg(long = "product")]
+    pub products: Vec<String>,
+
+    /// Максимальное количество новых записей за запуск.
+    #[arg(long)]
+    pub limit: Option<usize>,
+
+    /// Публиковать ответы без одобрения.
+    #[arg(long)]
+    pub no_approval: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum FeedbackKind {
+    Question,
+    Review,
+}
+
+impl FeedbackKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Question => "question",
+            Self::Review => "review",
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TemplatesCommand {
+    /// Вывести промпт, полученный применением шаблона к данным товара.
+    Render {
+        /// Путь к шаблону.
+        template: String,
+
+        /// Идентификатор товара.
+        #[arg(long)]
+        product: String,
+
+        /// Текст вопроса (переменная `question`).
+        #[arg(long, default_value = "")]
+        question: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DbCommand {
+    /// Выгрузить таблицу в JSON.
+    Export {
+        #[arg(value_enum)]
+        table: DbTable,
+
+        /// Файл для записи. Если не задан, вывод в stdout.
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+
+    /// Отчёт о вызовах AI провайдера: токены, стоимость и задержка.
+    Usage {
+        /// Группировка строк отчёта.
+        #[arg(long, value_enum, default_value = "day")]
+        by: UsageBy,
+
+        /// Начало периода: `YYYY-MM-DD` или Unix timestamp. По умолчанию начало текущего месяца.
+        #[arg(long, value_parser = parse_date_from)]
+        from: Option<u64>,
+
+        /// Конец периода (включительно): `YYYY-MM-DD` или Unix timestamp.
+        #[arg(long, value_parser = parse_date_to)]
+        to: Option<u64>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum DbTable {
+    Summaries,
+    Feedback,
+    AiCalls,
+    Shops,
+    Products,
+    Answers,
+    PublishAttempts,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum UsageBy {
+    Day,
+    Model,
+    Shop,
+    Task,
+}
+
+impl UsageBy {
+    fn group(&self) -> db::UsageGroup {
+        match self {
+            Self::Day => db::UsageGroup::Day,
+            Self::Model => db::UsageGroup::Model,
+            Self::Shop => db::UsageGroup::Shop,
+            Self::Task => db::UsageGroup::Task,
+        }
+    }
+}
+
+fn parse_key_value(s: &str) -> std::result::Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .ok_or_else(|| format!("expected key=value, got `{s}`"))
+}
+
+/// Разбирает дату `YYYY-MM-DD` (начало дня UTC) или Unix timestamp.
+fn parse_date_from(s: &str) -> std::result::Result<u64, String> {
+    if let Ok(ts) = s.parse::<u64>() {
+        return Ok(ts);
+    }
+
+    let rfc3339 = time::format_description::well_known::Rfc3339;
+    time::OffsetDateTime::parse(&format!("{s}T00:00:00Z"), &rfc3339)
+        .map(|d| d.unix_timestamp().max(0) as u64)
+        .map_err(|_| format!("expected YYYY-MM-DD or unix timestamp, got `{s}`"))
+}
+
+/// Разбирает дату `YYYY-MM-DD` (конец дня UTC) или Unix timestamp.
+fn parse_date_to(s: &str) -> std::result::Result<u64, String> {
+    if let Ok(ts) = s.parse::<u64>() {
+        return Ok(ts);
+    }
+
+    parse_date_from(s).map(|ts| ts + 86_399)
+}
+
+#[test]
+fn parse_date_test() {
+    assert_eq!(parse_date_from("1700000000"), Ok(1_700_000_000));
+    assert_eq!(parse_date_from("2024-01-02"), Ok(1_704_153_600));
+    assert_eq!(parse_date_to("2024-01-02"), Ok(1_704_239_999));
+    assert!(parse_date_from("02.01.2024").is_err());
+}
+
+/// Код завершения для ошибки выполнения команды.
+pub fn exit_code(e: &Error) -> u8 {
+    match e {
+        Error::Io(_) | Error::Tera(_) | Error::Lua(_) | Error::Json(_) | Error::Config(_) => {
+            EXIT_CONFIG
+        }
+        Error::Reqwest(_)
+        | Error::OzonSellerApi(_)
+        | Error::WbSellerApi(_)
+        | Error::AiProvider(_)
+        | Error::ProductCtxData(_)
+        | Error::Cassette(_) => EXIT_API,
+        Error::Sqlite(_) => EXIT_DB,
+        Error::MissingRequiredField(_) | Error::InvalidAnswer(_) | Error::Budget(_) => EXIT_FAILURE,
+    }
+}
+
+/// Команда вместе с итоговой конфигурацией (файл, `.env`, окружение и аргументы).
+struct App<'a> {
+    cli: &'a Cli,
+    cfg: Config,
+    /// Общий HTTP клиент клиентов маркетплейсов.
+    http: reqwest::Client,
+    /// HTTP клиент AI провайдера: общий клиент или клиент с прокси `llm_config.proxy`.
+    llm_http: reqwest::Client,
+}
+
+impl Cli {
+    /// Загружает конфигурацию и применяет к ней аргументы командной строки.
+    pub fn load_config(&self) -> Result<Config> {
+        let mut cfg = Config::load(self.config.as_deref())?;
+
+        if let Some(place) = self.place {
+            cfg.place = place.str_symbol().into();
+        }
+        if let Some(model) = &self.model {
+            cfg.llm_config.model = model.clone();
+        }
+        if let Some(path) = &self.db {
+            cfg.db.path = path.clone();
+        }
+        if let Command::Observe(args) = &self.command {
+            if let Some(v) = args.question_interval {
+                cfg.observer.question_interval_secs = v;
+            }
+            if let Some(v) = args.review_interval {
+                cfg.observer.review_interval_secs = v;
+            }
+            if args.backlog {
+                cfg.observer.process_backlog = true;
+            }
+        }
+
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /// Выполняет команду и возвращает код завершения.
+    pub async fn run(self) -> ExitCode {
+        let res = async {
+            let cfg = self.load_config()?;
+            db::configure(&cfg.db)?;
+
+            let http = cfg.http.client(None)?;
+            let llm_http = match cfg.llm_config.proxy.as_deref() {
+                Some(proxy) => cfg.http.client(Some(proxy))?,
+                None => http.clone(),
+            };
+
+            let app = App {
+                cli: &self,
+                cfg,
+                http,
+                llm_http,
+            };
+            app.execute().await
+        };
+
+        match res.await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("Ошибка: {e}");
+                ExitCode::from(exit_code(&e))
+            }
+        }
+    }
+}
+
+impl App<'_> {
+    /// Реестр магазинов с применёнными аргументами командной строки.
+    async fn shops(&self) -> Result<ShopRegistry> {
+        let mut shops = ShopRegistry::from_config(&self.cfg, &self.http)?;
+
+        for shop in shops.iter_mut() {
+            if let Some(model) = &self.cli.model {
+                shop.model = model.clone();
+            }
+            if let Command::Observe(args) = &self.cli.command {
+                if let Some(v) = &args.question_template {
+                    shop.question_template = v.clone();
+                }
+                if let Some(v) = &args.review_template {
+                    shop.review_template = v.clone();
+                }
+                if let Some(v) = &args.policy {
+                    shop.policy = Some(v.clone());
+                }
+            }
+
+            db::upsert_shop(&shop.id, shop.scli.str_symbol()).await?;
+        }
+
+        Ok(shops)
+    }
+
+    /// Магазин, выбранный `--shop`.
+    async fn shop(&self) -> Result<Shop> {
+        self.shops()
+            .await?
+            .select(self.cli.shop.as_deref())
+            .cloned()
+    }
+
+    fn router(&self) -> Result<Arc<ModelRouter>> {
+        Ok(Arc::new(ModelRouter::from_config(
+            &self.cfg.llm_config,
+            self.llm_http.clone(),
+        )?))
+    }
+
+    async fn execute(&self) -> Result<()> {
+        match &self.cli.command {
+            Command::Summarize { script, template } | Command::Resummarize { script, template } => {
+                let args = HashMap::from([
+                    ("template".to_string(), template.clone()),
+                    ("model".to_string(), self.shop().await?.model),
+                ]);
+                self.run_script(script, args).await
+            }
+            Command::Observe(args) => self.observe(args).await,
+            Command::Backlog(args) => self.backlog(args).await,
+            Command::Ask {
+                product,
+                question,
+                template,
+                stream,
+            } => {
+                self.ask(product, question.as_deref(), template.as_deref(), *stream)
+                    .await
+            }
+            Command::Answer {
+                kind,
+                id,
+                text,
+                product,
+            } => {
+                let scli = self.shop().await?.scli;
+                match kind {
+                    FeedbackKind::Question => {
+                        scli.answer_question(id, text, product.as_deref()).await
+                    }
+                    FeedbackKind::Review => scli.answer_review(id, text).await,
+                }
+            }
+            Command::Serve { addr } => {
+                println!("Веб-интерфейс: http://{addr}");
+                let state = Arc::new(webapp::AppState::new(&self.cfg.templates.dir));
+                webapp::run(addr.as_str(), state)
+                    .await
+                    .map_err(|e| Error::Io(std::io::Error::other(e)))
+            }
+            Command::Templates(TemplatesCommand::Render {
+                template,
+                product,
+                question,
+            }) => {
+                let template = std::fs::read_to_string(template)?;
+                let ctx = ProductContext::load(&self.shop().await?, product).await?;
+
+                let mut tera_ctx = Context::from_serialize(&ctx)?;
+                tera_ctx.insert("question", question);
+
+                println!("{}", Tera::one_off(&template, &tera_ctx, false)?);
+                Ok(())
+            }
+            Command::Db(DbCommand::Export { table, output }) => {
+                let json = match table {
+                    DbTable::Summaries => {
+                        serde_json::to_string_pretty(&db::select_all_product_ai_summaries().await?)?
+                    }
+                    DbTable::Feedback => {
+                        serde_json::to_string_pretty(&db::select_all_feedback().await?)?
+                    }
+                    DbTable::AiCalls => {
+                        serde_json::to_string_pretty(&db::select_all_ai_calls().await?)?
+                    }
+                    DbTable::Shops => serde_json::to_string_pretty(&db::select_all_shops().await?)?,
+                    DbTable::Products => {
+                        serde_json::to_string_pretty(&db::select_all_products().await?)?
+                    }
+                    DbTable::Answers => {
+                        serde_json::to_string_pretty(&db::select_all_answers().await?)?
+                    }
+                    DbTable::PublishAttempts => {
+                        serde_json::to_string_pretty(&db::select_all_publish_attempts().await?)?
+                    }
+                };
+
+                match output {
+                    Some(path) => std::fs::write(path, json)?,
+                    None => println!("{json}"),
+                }
+                Ok(())
+            }
+            Command::Db(DbCommand::Usage { by, from, to }) => {
+                let from = from.unwrap_or_else(genai::month_start);
+                let to = to.unwrap_or(u64::MAX >> 1);
+
+                print_usage_report(&db::select_ai_usage_report(by.group(), from, to).await?);
+                Ok(())
+            }
+            Command::Run { script, args } => {
+                self.run_script(script, args.iter().cloned().collect())
+                    .await
+            }
+        }
+    }
+
+    async fn run_script(&self, script: &str, args: HashMap<String, String>) -> Result<()> {
+        let shop = self.shop().await?;
+        let env = JobEnv {
+            model: shop.model.clone(),
+            shop,
+            router: self.router()?,
+            templates_dir: self.cfg.templates.dir.clone(),
+        };
+
+        LuaJob::from_file(script, env)?.run(&args).await
+    }
+
+    /// Параметры обработчика обратной связи магазина `shop`.
+    fn params(&self, shop: &Shop) -> Params {
+        Params {
+            question_template: shop.question_template.clone(),
+            review_template: shop.review_template.clone(),
+            model: shop.model.clone(),
+            question_interval: Duration::from_secs(self.cfg.observer.question_interval_secs),
+            review_interval: Duration::from_secs(self.cfg.observer.review_interval_secs),
+            policy_script: shop.policy.clone(),
+            auto_approve: shop.auto_approve.clone(),
+            process_backlog: self.cfg.observer.process_backlog,
+            retry: self.cfg.observer.retry(),
+            few_shot: self.cfg.templates.few_shot,
+            structured: self.cfg.templates.structured,
+            min_confidence: self.cfg.templates.min_confidence,
+            tools: self.cfg.templates.tools,
+            max_tool_steps: self.cfg.templates.max_tool_steps,
+            ..Default::default()
+        }
+    }
+
+    /// Запускает обработчики выбранного магазина или всех магазинов конфигурации.
+    /// Завершается, когда завершились обработчики всех магазинов.
+    async fn observe(&self, args: &ObserveArgs) -> Result<()> {
+        let shops = self.shops().await?;
+        let shops = match self.cli.shop.as_deref() {
+            Some(id) => vec![shops.select(Some(id))?.clone()],
+            None => shops.iter().cloned().collect(),
+        };
+
+        let router = self.router()?;
+        let mut tasks = tokio::task::JoinSet::new();
+
+        for shop in shops {
+            let params = Params {
+                require_approval: !args.no_approval,
+                ..self.params(&shop)
+            };
+
+            let id = shop.id.clone();
+            let controller = FeedbackController::new(shop, router.clone(), params)?;
+            tasks.spawn(async move { (id, controller.run().await) });
+        }
+
+        let mut res = Ok(());
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((_, Ok(()))) => {}
+                Ok((id, Err(e))) => {
+                    eprintln!("Ошибка обработчика магазина {id}: {e}");
+                    res = Err(e);
+                }
+                Err(e) => res = Err(Error::Io(std::io::Error::other(e))),
+            }
+        }
+
+        res
+    }
+
+    async fn backlog(&self, args: &BacklogArgs) -> Result<()> {
+        let shop = self.shop().await?;
+        let params = Params {
+            require_approval: !args.no_approval,
+            ..self.params(&shop)
+        };
+
+        let filter = UnansweredFilter {
+            kind: args.kind.map(|k| k.as_str().to_string()),
+            date_from: args.from,
+            date_to: args.to,
+            product_ids: args.products.clone(),
+        };
+
+        FeedbackController::new(shop, self.router()?, params)?
+            .process_backlog(&filter, args.limit)
+            .await
+            .map(|_| ())
+    }
+
+    async fn ask(
+        &self,
+        product: &str,
+        question: Option<&str>,
+        template: Option<&str>,
+        stream: bool,
+    ) -> Result<()> {
+        let shop = self.shop().await?;
+        let mut params = self.params(&shop);
+        if let Some(template) = template {
+            params.question_template = template.to_string();
+        }
+
+        let controller = FeedbackController::new(shop, self.router()?, params)?;
+
+        let new_question = |text: &str| {
+            NewFeedback::Question(NewQuestion {
+                id: String::new(),
+                product_id: product.to_string(),
+                author_name: DEFAULT_AUTHOR_NAME.to_string(),
+                text: text.trim().to_string(),
+                published_at: 0,
+            })
+        };
+
+        if let Some(text) = question {
+            return print_answer(&controller, &new_question(text), stream).await;
+        }
+
+        loop {
+            let mut text = String::new();
+            println!("Enter your question: ");
+            if std::io::stdin().read_line(&mut text)? == 0 {
+                return Ok(());
+            }
+
+            println!("Ожидание ответа AI провайдера...");
+            println!("\nAnswer to question:");
+            if let Err(e) = print_answer(&controller, &new_question(&text), stream).await {
+                eprintln!("Ошибка генерации ответа: {e}");
+            }
+        }
+    }
+}
+
+/// Выводит отчёт о вызовах AI провайдера с итоговой строкой.
+fn print_usage_report(rows: &[db::UsageReportRow]) {
+    println!(
+        "{:<40} {:>8} {:>8} {:>12} {:>12} {:>12} {:>10}",
+        "", "вызовы", "ошибки", "токены (з)", "токены (о)", "стоимость", "мс"
+    );
+
+    let print_row = |key: &str, calls, errors, prompt, completion, cost: f64, latency: f64| {
+        println!(
+            "{key:<40} {calls:>8} {errors:>8} {prompt:>12} {completion:>12} {cost:>12.4} {latency:>10.0}"
+        );
+    };
+
+    for row in rows {
+        print_row(
+            &row.key,
+            row.calls,
+            row.errors,
+            row.prompt_tokens,
+            row.completion_tokens,
+            row.cost,
+            row.avg_latency_ms,
+        );
+    }
+
+    let calls = rows.iter().map(|r| r.calls).sum::<u64>();
+    // Сумма пустого итератора f64 равна -0.0, поэтому суммы считаются от 0.0.
+    let latency = rows
+        .iter()
+        .fold(0.0, |acc, r| acc + r.avg_latency_ms * r.calls as f64)
+        / calls.max(1) as f64;
+
+    print_row(
+        "итого",
+        calls,
+        rows.iter().map(|r| r.errors).sum(),
+        rows.iter().map(|r| r.prompt_tokens).sum(),
+        rows.iter().map(|r| r.completion_tokens).sum(),
+        rows.iter().fold(0.0, |acc, r| acc + r.cost),
+        latency,
+    );
+}
+
+/// Выводит сгенерированный ответ. В потоковом режиме фрагменты выводятся по мере генерации.
+async fn print_answer(
+    controller: &FeedbackController,
+    feedback: &NewFeedback,
+    stream: bool,
+) -> Result<()> {
+    if !stream {
+        let answer = controller.generate_answer(feedback).await?;
+        eprintln!("Модель: {}", answer.model.unwrap_or_default());
+        if let (Some(sentiment), Some(confidence)) = (answer.sentiment, answer.confidence) {
+            eprintln!(
+                "Тональность: {}, уверенность: {confidence:.2}",
+                sentiment.as_str()
+            );
+        }
+        if let Some(reason) = answer.escalate {
+            eprintln!("Требуется проверка человеком: {reason}");
+        }
+        println!("{}", answer.text);
+        return Ok(());
+    }
+
+    let (model, mut rx) = controller.generate_answer_stream(feedback).await?;
+    eprintln!("Модель: {model}");
+    let mut stdout = std::io::stdout();
+
+    while let Some(event) = rx.recv().await {
+        if let ChatStreamEvent::Delta { content, .. } = event? {
+            stdout.write_all(content.as_bytes())?;
+            stdout.flush()?;
+        }
+    }
+    println!();
+
+    Ok(())
+}
@@ -1,20 +1,778 @@
+use crate::{
+    controller::feedback::AutoApproveRule,
+    error::{Error, Result},
+    sellerapi::{Cassette, CassetteMode, OzonApiUrls, RetryPolicy, WbApiUrls},
+};
 use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path, str::FromStr, time::Duration};
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// Файл конфигурации, который загружается, если путь не указан явно и файл существует.
+pub const DEFAULT_CONFIG_PATH: &str = "blueberry.toml";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OzonSellerCredentials {
     pub client_id: String,
     pub api_key: String,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WbSellerCredentials {
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LLmConfig {
+    /// Тип API: `"openai"` (OpenAI-совместимый `/chat/completions`: OpenRouter, vLLM и т.п.),
+    /// `"ollama"` (нативный API Ollama) или `"mock"` (детерминированная локальная заглушка).
+    pub backend: String,
     pub base_url: String,
     pub api_key: String,
+    /// Модель по умолчанию.
     pub model: String,
+    /// URL прокси для запросов к AI провайдеру.
+    pub proxy: Option<String>,
+    /// Таймаут запроса, секунды.
+    pub timeout_secs: u64,
+    /// Фиксированный ответ заглушки `"mock"`. Если не задан, заглушка повторяет
+    /// последнее сообщение пользователя.
+    pub mock_reply: Option<String>,
+    /// Резервные модели по задачам, которые используются по очереди,
+    /// если основная модель недоступна.
+    pub fallback: FallbackModels,
+    /// Пауза, в течение которой модель, вернувшая ошибку, не используется, секунды.
+    pub cooldown_secs: u64,
+    /// Цены моделей за 1000 токенов по имени модели.
+    pub prices: HashMap<String, ModelPrice>,
+    /// Месячный бюджет на вызовы AI провайдера в единицах `prices`. При превышении
+    /// некритичные задачи (AI-сводки о товарах) не выполняются до начала следующего месяца.
+    pub monthly_budget: Option<f64>,
+}
+
+/// Цена модели за 1000 токенов.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelPrice {
+    /// Токены запроса.
+    pub prompt: f64,
+    /// Токены ответа.
+    pub completion: f64,
+}
+
+impl ModelPrice {
+    /// Стоимость вызова с `prompt_tokens` токенами запроса и `completion_tokens` токенами ответа.
+    pub fn cost(&self, prompt_tokens: u32, completion_tokens: u32) -> f64 {
+        (self.prompt * prompt_tokens as f64 + self.completion * completion_tokens as f64) / 1000.0
+    }
+}
+
+/// Резервные модели по задачам в порядке очереди.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FallbackModels {
+    /// AI-сводки о товарах.
+    pub summary: Vec<String>,
+    /// Ответы на вопросы.
+    pub question: Vec<String>,
+    /// Ответы на отзывы.
+    pub review: Vec<String>,
+}
+
+impl Default for LLmConfig {
+    fn default() -> Self {
+        Self {
+            backend: "openai".into(),
+            base_url: String::new(),
+            api_key: String::new(),
+            model: "deepseek/deepseek-r1-0528:free".into(),
+            proxy: None,
+            timeout_secs: 60,
+            mock_reply: None,
+            fallback: Default::default(),
+            cooldown_secs: 60,
+            prices: HashMap::new(),
+            monthly_budget: None,
+        }
+    }
+}
+
+impl LLmConfig {
+    #[inline]
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    #[inline]
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.cooldown_secs)
+    }
+}
+
+/// Общие настройки HTTP клиента, который используется клиентами маркетплейсов
+/// и AI провайдером.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Таймаут запроса к API маркетплейса, секунды.
+    pub timeout_secs: u64,
+    /// Таймаут установки соединения, секунды.
+    pub connect_timeout_secs: u64,
+    /// Заголовок `User-Agent`.
+    pub user_agent: String,
+    /// URL прокси для всех запросов. Для AI провайдера переопределяется `llm_config.proxy`.
+    pub proxy: Option<String>,
+    /// Время хранения неиспользуемого соединения в пуле, секунды.
+    pub pool_idle_timeout_secs: u64,
+    /// Максимальное количество неиспользуемых соединений с одним хостом.
+    pub pool_max_idle_per_host: usize,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 5,
+            connect_timeout_secs: 10,
+            user_agent: concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")).into(),
+            proxy: None,
+            pool_idle_timeout_secs: 90,
+            pool_max_idle_per_host: 8,
+        }
+    }
+}
+
+impl HttpConfig {
+    #[inline]
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    /// Создаёт HTTP клиент с прокси `proxy` или, если он не задан, с прокси `self.proxy`.
+    /// Клиент хранит пул соединений, поэтому создаётся один раз и клонируется.
+    pub fn client(&self, proxy: Option<&str>) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(self.connect_timeout_secs))
+            .user_agent(&self.user_agent)
+            .pool_idle_timeout(Duration::from_secs(self.pool_idle_timeout_secs))
+            .pool_max_idle_per_host(self.pool_max_idle_per_host);
+
+        if let Some(proxy) = proxy.or(self.proxy.as_deref()) {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| Error::Config(format!("invalid proxy \"{proxy}\": {e}")))?;
+            builder = builder.proxy(proxy);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObserverConfig {
+    /// Интервал опроса новых вопросов, секунды.
+    pub question_interval_secs: u64,
+    /// Интервал опроса новых отзывов, секунды.
+    pub review_interval_secs: u64,
+    /// При запуске обработать вопросы и отзывы, уже ожидающие ответа.
+    pub process_backlog: bool,
+    /// Пауза после первой ошибки запроса, миллисекунды. Далее удваивается.
+    pub retry_base_delay_ms: u64,
+    /// Максимальная пауза между повторами, секунды.
+    pub retry_max_delay_secs: u64,
+    /// Количество повторов запроса страницы при выгрузке ожидающей ответа обратной связи.
+    pub retry_max_retries: u32,
+}
+
+impl Default for ObserverConfig {
+    fn default() -> Self {
+        Self {
+            question_interval_secs: 11,
+            review_interval_secs: 7,
+            process_backlog: false,
+            retry_base_delay_ms: 500,
+            retry_max_delay_secs: 300,
+            retry_max_retries: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemplatesConfig {
+    /// Каталог шаблонов (веб-интерфейс и Lua задачи).
+    pub dir: String,
+    /// Шаблон промпта для ответа на вопрос.
+    pub question: String,
+    /// Шаблон промпта для ответа на отзыв.
+    pub review: String,
+    /// Количество примеров одобренных ответов (few-shot), добавляемых к промпту.
+    pub few_shot: u32,
+    /// Запрашивать ответ на обратную связь в формате JSON с оценкой уверенности,
+    /// тональностью и признаком передачи человеку.
+    pub structured: bool,
+    /// Минимальная уверенность модели в структурированном ответе: ответ с меньшей
+    /// уверенностью передаётся человеку.
+    pub min_confidence: f32,
+    /// Разрешить модели запрашивать данные товара, цену, остатки и похожие ответы
+    /// с помощью инструментов вместо передачи данных товара в промпте.
+    pub tools: bool,
+    /// Максимальное количество запросов к модели с вызовами инструментов на один ответ.
+    pub max_tool_steps: u32,
+}
+
+impl ObserverConfig {
+    pub fn retry(&self) -> RetryPolicy {
+        RetryPolicy {
+            base_delay: Duration::from_millis(self.retry_base_delay_ms),
+            max_delay: Duration::from_secs(self.retry_max_delay_secs),
+            max_retries: self.retry_max_retries,
+        }
+    }
+}
+
+impl Default for TemplatesConfig {
+    fn default() -> Self {
+        Self {
+            dir: "templates".into(),
+            question: "templates/question.j2".into(),
+            review: "templates/review.j2".into(),
+            few_shot: 3,
+            structured: false,
+            min_confidence: 0.5,
+            tools: false,
+            max_tool_steps: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DbConfig {
+    /// Путь к файлу базы данных SQLite.
+    pub path: String,
+    /// Максимальное число одновременно открытых соединений.
+    pub pool_size: usize,
+    /// Время ожидания блокировки базы данных другим соединением, миллисекунды.
+    pub busy_timeout_ms: u64,
+}
+
+impl Default for DbConfig {
+    fn default() -> Self {
+        Self {
+            path: ".db".into(),
+            pool_size: 4,
+            busy_timeout_ms: 5000,
+        }
+    }
+}
+
+/// Запись и воспроизведение ответов API маркетплейсов (см. `Cassette`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CassetteConfig {
+    /// Режим: `"off"`, `"record"` или `"replay"`.
+    pub mode: CassetteMode,
+    /// Каталог файлов. Ответы каждого магазина хранятся в подкаталоге `{place}/{shop_id}`.
+    pub dir: String,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+impl Default for CassetteConfig {
+    fn default() -> Self {
+        Self {
+            mode: CassetteMode::Off,
+            dir: "tests/cassettes".into(),
+        }
+    }
+}
+
+impl CassetteConfig {
+    /// Кассета клиента магазина `shop`.
+    pub fn cassette(&self, shop: &ShopConfig) -> Cassette {
+        Cassette::new(
+            self.mode,
+            Path::new(&self.dir).join(&shop.place).join(&shop.id),
+        )
+    }
+}
+
+/// Магазин — кабинет продавца на маркетплейсе.
+/// Незаданные параметры берутся из общих разделов конфигурации.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShopConfig {
+    /// Идентификатор магазина. Используется как пространство имён записей в базе данных.
+    pub id: String,
+    /// Маркетплейс: `"oz"` или `"wb"`.
+    pub place: String,
+    pub ozon_seller_credentials: OzonSellerCredentials,
+    pub wb_seller_credentials: WbSellerCredentials,
+    /// Модель AI провайдера.
+    pub model: Option<String>,
+    /// Шаблон промпта для ответа на вопрос.
+    pub question_template: Option<String>,
+    /// Шаблон промпта для ответа на отзыв.
+    pub review_template: Option<String>,
+    /// Lua скрипт политики обработки.
+    pub policy: Option<String>,
+    /// Правила автоматического одобрения черновиков (`[[shops.auto_approve]]` в TOML).
+    pub auto_approve: Option<Vec<AutoApproveRule>>,
+}
+
+/// Конфигурация приложения.
+///
+/// Источники применяются по порядку, каждый следующий переопределяет предыдущий:
+/// 1. файл TOML или JSON (`Config::from_file`),
+/// 2. файл `.env`,
+/// 3. переменные окружения (`Config::apply_env`),
+/// 4. аргументы командной строки.
+///
+/// Если список `shops` пуст, используется один магазин с идентификатором `place`
+/// и учётными данными из `ozon_seller_credentials` / `wb_seller_credentials`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    /// Маркетплейс по умолчанию: `"oz"` или `"wb"`.
+    pub place: String,
     pub ozon_seller_credentials: OzonSellerCredentials,
+    pub wb_seller_credentials: WbSellerCredentials,
+    /// Базовые URL Ozon Seller API.
+    pub ozon_api: OzonApiUrls,
+    /// Базовые URL WB Seller API.
+    pub wb_api: WbApiUrls,
     pub llm_config: LLmConfig,
+    pub http: HttpConfig,
+    pub observer: ObserverConfig,
+    pub templates: TemplatesConfig,
+    pub db: DbConfig,
+    pub cassette: CassetteConfig,
+    /// Lua скрипт политики обработки по умолчанию.
+    pub policy: Option<String>,
+    /// Правила автоматического одобрения черновиков по умолчанию (`[[auto_approve]]` в TOML).
+    /// Пустой список отключает автоматическое одобрение.
+    pub auto_approve: Vec<AutoApproveRule>,
+    /// Магазины (`[[shops]]` в TOML).
+    pub shops: Vec<ShopConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            place: "wb".into(),
+            ozon_seller_credentials: Default::default(),
+            wb_seller_credentials: Default::default(),
+            ozon_api: Default::default(),
+            wb_api: Default::default(),
+            llm_config: Default::default(),
+            http: Default::default(),
+            observer: Default::default(),
+            templates: Default::default(),
+            db: Default::default(),
+            cassette: Default::default(),
+            policy: None,
+            auto_approve: vec![AutoApproveRule::five_star_without_text()],
+            shops: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Загружает конфигурацию из файла `path` (или `DEFAULT_CONFIG_PATH`, если он существует),
+    /// затем применяет `.env` и переменные окружения.
+    pub fn load(path: Option<&str>) -> Result<Self> {
+        let mut cfg = match path {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(DEFAULT_CONFIG_PATH)?
+            }
+            None => Self::default(),
+        };
+
+        let _ = dotenv::dotenv().ok();
+        cfg.apply_env()?;
+
+        Ok(cfg)
+    }
+
+    /// Читает конфигурацию из файла. Формат определяется по расширению: `.json` или TOML.
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| Error::Config(format!("failed to read {path}: {e}")))?;
+
+        if path.ends_with(".json") {
+            serde_json::from_str(&content).map_err(|e| Error::Config(format!("{path}: {e}")))
+        } else {
+            toml::from_str(&content).map_err(|e| Error::Config(format!("{path}: {e}")))
+        }
+    }
+
+    /// Применяет переменные окружения:
+    /// - `BLUEBERRY_PLACE` — маркетплейс по умолчанию,
+    /// - `OZON_SELLER_CLIENT_ID`, `OZON_SELLER_API_KEY` — учётные данные Ozon,
+    /// - `WB_SELLER_API_TOKEN` — токен Wildberries,
+    /// - `OZON_SELLER_API_URL` — базовый URL Ozon Seller API,
+    /// - `WB_SELLER_API_URL` — базовый URL всех хостов WB Seller API (например, локального сервера),
+    /// - `AI_PROVIDER_BACKEND`, `AI_PROVIDER_BASE_URL`, `AI_PROVIDER_API_KEY`, `AI_PROVIDER_MODEL`,
+    ///   `AI_PROVIDER_PROXY`, `AI_PROVIDER_TIMEOUT` — AI провайдер,
+    /// - `BLUEBERRY_QUESTION_INTERVAL`, `BLUEBERRY_REVIEW_INTERVAL` — интервалы наблюдателей,
+    /// - `BLUEBERRY_PROCESS_BACKLOG` — обработать ожидающую ответа обратную связь при запуске,
+    /// - `BLUEBERRY_TEMPLATES_DIR`, `BLUEBERRY_QUESTION_TEMPLATE`, `BLUEBERRY_REVIEW_TEMPLATE` — шаблоны,
+    /// - `BLUEBERRY_DB_PATH` — путь к базе данных,
+    /// - `BLUEBERRY_CASSETTE_MODE`, `BLUEBERRY_CASSETTE_DIR` — запись и воспроизведение ответов API.
+    pub fn apply_env(&mut self) -> Result<()> {
+        set_from_env("BLUEBERRY_PLACE", &mut self.place);
+
+        set_from_env(
+            "OZON_SELLER_CLIENT_ID",
+            &mut self.ozon_seller_credentials.client_id,
+        );
+        set_from_env(
+            "OZON_SELLER_API_KEY",
+            &mut self.ozon_seller_credentials.api_key,
+        );
+        set_from_env("WB_SELLER_API_TOKEN", &mut self.wb_seller_credentials.token);
+        set_from_env("OZON_SELLER_API_URL", &mut self.ozon_api.seller);
+        if let Ok(v) = std::env::var("WB_SELLER_API_URL")
+            && !v.is_empty()
+        {
+            self.wb_api = WbApiUrls::all(&v);
+        }
+
+        set_from_env("AI_PROVIDER_BACKEND", &mut self.llm_config.backend);
+        set_from_env("AI_PROVIDER_BASE_URL", &mut self.llm_config.base_url);
+        set_from_env("AI_PROVIDER_API_KEY", &mut self.llm_config.api_key);
+        set_from_env("AI_PROVIDER_MODEL", &mut self.llm_config.model);
+        if let Ok(v) = std::env::var("AI_PROVIDER_PROXY") {
+            self.llm_config.proxy = Some(v).filter(|v| !v.is_empty());
+        }
+        parse_from_env("AI_PROVIDER_TIMEOUT", &mut self.llm_config.timeout_secs)?;
+
+        parse_from_env(
+            "BLUEBERRY_QUESTION_INTERVAL",
+            &mut self.observer.question_interval_secs,
+        )?;
+        parse_from_env(
+            "BLUEBERRY_REVIEW_INTERVAL",
+            &mut self.observer.review_interval_secs,
+        )?;
+        parse_from_env(
+            "BLUEBERRY_PROCESS_BACKLOG",
+            &mut self.observer.process_backlog,
+        )?;
+
+        set_from_env("BLUEBERRY_TEMPLATES_DIR", &mut self.templates.dir);
+        set_from_env("BLUEBERRY_QUESTION_TEMPLATE", &mut self.templates.question);
+        set_from_env("BLUEBERRY_REVIEW_TEMPLATE", &mut self.templates.review);
+
+        set_from_env("BLUEBERRY_DB_PATH", &mut self.db.path);
+
+        parse_from_env("BLUEBERRY_CASSETTE_MODE", &mut self.cassette.mode)?;
+        set_from_env("BLUEBERRY_CASSETTE_DIR", &mut self.cassette.dir);
+
+        Ok(())
+    }
+
+    /// Магазины из конфигурации, либо один магазин из общих параметров, если список пуст.
+    pub fn shops(&self) -> Vec<ShopConfig> {
+        if !self.shops.is_empty() {
+            return self.shops.clone();
+        }
+
+        vec![ShopConfig {
+            id: self.place.clone(),
+            place: self.place.clone(),
+            ozon_seller_credentials: self.ozon_seller_credentials.clone(),
+            wb_seller_credentials: self.wb_seller_credentials.clone(),
+            ..Default::default()
+        }]
+    }
+
+    /// Проверяет общие параметры, не зависящие от выполняемой команды.
+    pub fn validate(&self) -> Result<()> {
+        validate_place("place", &self.place)?;
+        validate_auto_approve("auto_approve", &self.auto_approve)?;
+
+        let mut ids = std::collections::HashSet::new();
+        for shop in &self.shops {
+            if shop.id.is_empty() || shop.id.contains('/') {
+                return Err(Error::Config(format!(
+                    "shop id must be non-empty and must not contain '/', got \"{}\"",
+                    shop.id
+                )));
+            }
+            if !ids.insert(shop.id.as_str()) {
+                return Err(Error::Config(format!("duplicate shop id \"{}\"", shop.id)));
+            }
+            validate_place(&format!("shops.{}.place", shop.id), &shop.place)?;
+            if let Some(rules) = &shop.auto_approve {
+                validate_auto_approve(&format!("shops.{}.auto_approve", shop.id), rules)?;
+            }
+        }
+
+        if !(0.0..=1.0).contains(&self.templates.min_confidence) {
+            return Err(Error::Config(
+                "templates.min_confidence must be between 0 and 1".into(),
+            ));
+        }
+        if self.observer.question_interval_secs == 0 || self.observer.review_interval_secs == 0 {
+            return Err(Error::Config("observer intervals must be positive".into()));
+        }
+        if self.observer.retry_base_delay_ms == 0
+            || self.observer.retry_max_delay_secs.saturating_mul(1000)
+                < self.observer.retry_base_delay_ms
+        {
+            return Err(Error::Config(
+                "observer retry delays must be positive and retry_base_delay_ms must not exceed retry_max_delay_secs".into(),
+            ));
+        }
+        if self.http.timeout_secs == 0 || self.http.connect_timeout_secs == 0 {
+            return Err(Error::Config("http timeouts must be positive".into()));
+        }
+        if self.llm_config.timeout_secs == 0 {
+            return Err(Error::Config(
+                "llm_config.timeout_secs must be positive".into(),
+            ));
+        }
+        if self.llm_config.monthly_budget.is_some_and(|v| v <= 0.0)
+            || self
+                .llm_config
+                .prices
+                .values()
+                .any(|p| p.prompt < 0.0 || p.completion < 0.0)
+        {
+            return Err(Error::Config(
+                "llm_config.monthly_budget must be positive and prices must not be negative".into(),
+            ));
+        }
+        if self.db.path.is_empty() {
+            return Err(Error::Config("db.path is empty".into()));
+        }
+        if self.db.pool_size == 0 {
+            return Err(Error::Config("db.pool_size must be greater than 0".into()));
+        }
+        if self.cassette.mode != CassetteMode::Off && self.cassette.dir.is_empty() {
+            return Err(Error::Config("cassette.dir is empty".into()));
+        }
+
+        let urls = [
+            ("ozon_api.seller", &self.ozon_api.seller),
+            ("wb_api.content", &self.wb_api.content),
+            ("wb_api.prices", &self.wb_api.prices),
+            ("wb_api.statistics", &self.wb_api.statistics),
+            ("wb_api.feedbacks", &self.wb_api.feedbacks),
+        ];
+        for (name, url) in urls {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(Error::Config(format!(
+                    "{name} must be an http(s) URL, got \"{url}\""
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl OzonSellerCredentials {
+    pub fn validate(&self) -> Result<()> {
+        require(
+            "ozon_seller_credentials.client_id (OZON_SELLER_CLIENT_ID)",
+            &self.client_id,
+        )?;
+        require(
+            "ozon_seller_credentials.api_key (OZON_SELLER_API_KEY)",
+            &self.api_key,
+        )
+    }
+}
+
+impl WbSellerCredentials {
+    pub fn validate(&self) -> Result<()> {
+        require(
+            "wb_seller_credentials.token (WB_SELLER_API_TOKEN)",
+            &self.token,
+        )
+    }
+}
+
+impl LLmConfig {
+    pub fn validate(&self) -> Result<()> {
+        match self.backend.as_str() {
+            "openai" => {
+                require("llm_config.base_url (AI_PROVIDER_BASE_URL)", &self.base_url)?;
+                require("llm_config.api_key (AI_PROVIDER_API_KEY)", &self.api_key)?;
+            }
+            "ollama" => require("llm_config.base_url (AI_PROVIDER_BASE_URL)", &self.base_url)?,
+            "mock" => {}
+            backend => {
+                return Err(Error::Config(format!(
+                    "llm_config.backend must be \"openai\", \"ollama\" or \"mock\", got \"{backend}\""
+                )));
+            }
+        }
+        require("llm_config.model (AI_PROVIDER_MODEL)", &self.model)
+    }
+}
+
+fn validate_place(name: &str, place: &str) -> Result<()> {
+    if place != "oz" && place != "wb" {
+        return Err(Error::Config(format!(
+            "{name} must be \"oz\" or \"wb\", got \"{place}\""
+        )));
+    }
+    Ok(())
+}
+
+fn validate_auto_approve(name: &str, rules: &[AutoApproveRule]) -> Result<()> {
+    for rule in rules {
+        if let Some(kind) = rule.kind.as_deref()
+            && kind != "question"
+            && kind != "review"
+        {
+            return Err(Error::Config(format!(
+                "{name}.kind must be \"question\" or \"review\", got \"{kind}\""
+            )));
+        }
+
+        let scores = [rule.min_score, rule.max_score];
+        if scores.iter().flatten().any(|s| !(1.0..=5.0).contains(s))
+            || rule
+                .min_score
+                .zip(rule.max_score)
+                .is_some_and(|(min, max)| min > max)
+        {
+            return Err(Error::Config(format!(
+                "{name}: scores must be between 1 and 5 and min_score must not exceed max_score"
+            )));
+        }
+        if rule.kind.as_deref() == Some("question") && scores.iter().any(Option::is_some) {
+            return Err(Error::Config(format!(
+                "{name}: questions have no score, min_score and max_score apply only to reviews"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[inline]
+fn require(name: &str, value: &str) -> Result<()> {
+    if value.trim().is_empty() {
+        return Err(Error::Config(format!("{name} is not set")));
+    }
+    Ok(())
+}
+
+#[inline]
+fn set_from_env(name: &str, target: &mut String) {
+    if let Ok(v) = std::env::var(name) {
+        *target = v;
+    }
+}
+
+fn parse_from_env<T: FromStr>(name: &str, target: &mut T) -> Result<()> {
+    if let Ok(v) = std::env::var(name) {
+        *target = v
+            .trim()
+            .parse()
+            .map_err(|_| Error::Config(format!("{name}: invalid value \"{v}\"")))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn config_from_file_test() {
+    let path = std::env::temp_dir().join("blueberry_config_test.toml");
+    std::fs::write(
+        &path,
+        r#"
+place = "oz"
+
+[ozon_seller_credentials]
+client_id = "1"
+api_key = "key"
+
+[observer]
+question_interval_secs = 30
+"#,
+    )
+    .unwrap();
+
+    let cfg = Config::from_file(path.to_str().unwrap()).unwrap();
+
+    assert_eq!(cfg.place, "oz");
+    assert_eq!(cfg.observer.question_interval_secs, 30);
+    assert_eq!(cfg.observer.review_interval_secs, 7);
+    assert!(cfg.validate().is_ok());
+    assert!(cfg.ozon_seller_credentials.validate().is_ok());
+
+    let mut huge = cfg.clone();
+    huge.observer.retry_max_delay_secs = u64::MAX;
+    assert!(huge.validate().is_ok());
+    assert!(cfg.wb_seller_credentials.validate().is_err());
+
+    assert_eq!(cfg.shops().len(), 1);
+    assert_eq!(cfg.shops()[0].id, "oz");
+    assert_eq!(cfg.auto_approve.len(), 1);
+    assert_eq!(cfg.auto_approve[0].min_score, Some(5.0));
+
+    std::fs::write(
+        &path,
+        r#"
+[[auto_approve]]
+kind = "review"
+min_score = 4
+
+[[shops]]
+id = "oz-outlet"
+place = "oz"
+
+[[shops.auto_approve]]
+kind = "question"
+empty_text = true
+"#,
+    )
+    .unwrap();
+
+    let cfg = Config::from_file(path.to_str().unwrap()).unwrap();
+    assert!(cfg.validate().is_ok());
+    assert_eq!(cfg.auto_approve[0].min_score, Some(4.0));
+    assert!(!cfg.auto_approve[0].empty_text);
+    let rules = cfg.shops()[0].auto_approve.clone().unwrap();
+    assert_eq!(rules[0].kind.as_deref(), Some("question"));
+
+    for rule in [
+        "kind = \"answer\"",
+        "min_score = 6",
+        "min_score = 4\nmax_score = 3",
+        "kind = \"question\"\nmin_score = 5",
+    ] {
+        std::fs::write(&path, format!("[[auto_approve]]\n{rule}\n")).unwrap();
+        let cfg = Config::from_file(path.to_str().unwrap()).unwrap();
+        assert!(matches!(cfg.validate(), Err(Error::Config(_))), "{rule}");
+    }
+
+    std::fs::write(
+        &path,
+        r#"
+[[shops]]
+id = "wb-main"
+place = "wb"
+model = "m"
+
+[[shops]]
+id = "wb-main"
+place = "wb"
+"#,
+    )
+    .unwrap();
+
+    let cfg = Config::from_file(path.to_str().unwrap()).unwrap();
+    assert_eq!(cfg.shops()[0].model.as_deref(), Some("m"));
+    assert!(matches!(cfg.validate(), Err(Error::Config(_))));
+
+    std::fs::write(&path, "place = 1").unwrap();
+    assert!(matches!(
+        Config::from_file(path.to_str().unwrap()),
+        Err(Error::Config(_))
+    ));
 }
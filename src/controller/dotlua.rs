@@ -1 +1,787 @@
-pub fn apply_template() {}
+use crate::error::{Error, Result};
+use crate::{
+    db::{self, SummarySource},
+    genai::{self, ChatRequest, Message, ModelRouter, Task},
+    sellerapi::{SellerClient, abcmodels::ProductFormatInfo},
+    shop::Shop,
+};
+use mlua::{Function, HookTriggers, Lua, LuaOptions, LuaSerdeExt, StdLib, Table, Value, VmState};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Component, Path},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tera::{Context, Tera};
+
+/// Шаг, с которым срабатывает счётчик инструкций.
+const INSTRUCTION_HOOK_STEP: u32 = 1000;
+
+/// Функции базовой библиотеки, недоступные скриптам.
+const SANDBOX_REMOVED_GLOBALS: [&str; 5] =
+    ["dofile", "loadfile", "load", "require", "collectgarbage"];
+
+/// Ограничения песочницы Lua.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Разрешённые стандартные библиотеки.
+    pub libs: StdLib,
+    /// Лимит памяти в байтах.
+    pub memory: usize,
+    /// Лимит инструкций на один запуск (вызов хука или выполнение задачи).
+    pub instructions: u64,
+    /// Лимит времени на один запуск.
+    pub time: Option<Duration>,
+}
+
+impl Limits {
+    /// Ограничения для хуков политики: короткие синхронные вызовы.
+    pub fn policy() -> Self {
+        Self {
+            libs: StdLib::TABLE | StdLib::STRING | StdLib::MATH | StdLib::UTF8,
+            memory: 16 * 1024 * 1024,
+            instructions: 1_000_000,
+            time: Some(Duration::from_secs(1)),
+        }
+    }
+
+    /// Ограничения для задач автоматизации: длительные скрипты с обращениями к API.
+    pub fn job() -> Self {
+        Self {
+            libs: StdLib::TABLE | StdLib::STRING | StdLib::MATH | StdLib::UTF8 | StdLib::OS,
+            memory: 64 * 1024 * 1024,
+            instructions: 100_000_000,
+            time: Some(Duration::from_secs(6 * 60 * 60)),
+        }
+    }
+}
+
+/// Счётчики лимитов текущего запуска.
+#[derive(Debug, Default)]
+struct Budget {
+    instructions_left: u64,
+    deadline: Option<Instant>,
+}
+
+/// Экземпляр Lua с ограниченным набором библиотек, лимитом памяти
+/// и хуком, прерывающим выполнение при исчерпании инструкций или времени.
+struct Sandbox {
+    lua: Lua,
+    limits: Limits,
+    budget: Arc<Mutex<Budget>>,
+}
+
+impl Sandbox {
+    fn new(limits: Limits) -> Result<Self> {
+        let lua = Lua::new_with(limits.libs, LuaOptions::default())?;
+
+        lua.set_memory_limit(limits.memory)?;
+
+        let globals = lua.globals();
+        for name in SANDBOX_REMOVED_GLOBALS {
+            globals.set(name, Value::Nil)?;
+        }
+
+        // Из библиотеки os оставляем только функции времени.
+        if let Ok(os) = globals.get::<Table>("os") {
+            for name in [
+                "execute",
+                "exit",
+                "getenv",
+                "remove",
+                "rename",
+                "setlocale",
+                "tmpname",
+            ] {
+                os.set(name, Value::Nil)?;
+            }
+        }
+
+        let budget = Arc::new(Mutex::new(Budget::default()));
+
+        {
+            let budget = budget.clone();
+            lua.set_global_hook(
+                HookTriggers::new().every_nth_instruction(INSTRUCTION_HOOK_STEP),
+                move |_, _| {
+                    let mut budget = budget.lock().unwrap();
+
+                    budget.instructions_left = budget
+                        .instructions_left
+                        .checked_sub(INSTRUCTION_HOOK_STEP as u64)
+                        .ok_or_else(|| mlua::Error::runtime("instruction limit exceeded"))?;
+
+                    if budget.deadline.is_some_and(|d| Instant::now() >= d) {
+                        return Err(mlua::Error::runtime("time limit exceeded"));
+                    }
+
+                    Ok(VmState::Continue)
+                },
+            )?;
+        }
+
+        Ok(Self {
+            lua,
+            limits,
+            budget,
+        })
+    }
+
+    /// Восстанавливает лимиты перед новым запуском.
+    fn reset_budget(&self) {
+        let mut budget = self.budget.lock().unwrap();
+        budget.instructions_left = self.limits.instructions;
+        budget.deadline = self.limits.time.map(|t| Instant::now() + t);
+    }
+}
+
+/// Действие, которое хук требует выполнить с обратной связью.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookAction {
+    /// Продолжить обработку (с учётом `template`/`model`, если заданы).
+    #[default]
+    Continue,
+    /// Пропустить обратную связь без ответа.
+    Skip,
+    /// Использовать `text` в качестве ответа.
+    Rewrite,
+    /// Передать обратную связь человеку.
+    Escalate,
+}
+
+/// Решение хука. Хук возвращает таблицу вида
+/// `{ action = "skip" | "rewrite" | "escalate" | "continue", text = ..., reason = ..., template = ..., model = ... }`
+/// или `nil`, что равнозначно `{ action = "continue" }`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HookDecision {
+    #[serde(default)]
+    pub action: HookAction,
+    /// Текст ответа для `rewrite`.
+    pub text: Option<String>,
+    /// Причина для `skip` и `escalate`.
+    pub reason: Option<String>,
+    /// Путь к шаблону промпта, которым нужно сгенерировать ответ.
+    pub template: Option<String>,
+    /// Модель AI провайдера, которой нужно сгенерировать ответ.
+    pub model: Option<String>,
+}
+
+/// Политика обработки обратной связи, заданная Lua скриптом.
+///
+/// Скрипт может объявить глобальные функции:
+/// - `on_question(q, ctx)` — вызывается для нового вопроса (`NewQuestion`),
+/// - `on_review(r, ctx)` — вызывается для нового отзыва (`NewReview`),
+/// - `before_publish(answer)` — вызывается перед публикацией ответа.
+///
+/// `ctx` содержит `place`, `ai_summary` и, если он был запрошен, `product` (`ProductFormatInfo`).
+/// `answer` содержит `text`, `feedback` и `kind`.
+/// Каждая функция возвращает `HookDecision` в виде таблицы или `nil`.
+///
+/// Скрипт выполняется в песочнице (`Limits::policy`): доступны только библиотеки
+/// `table`, `string`, `math`, `utf8` и базовые функции без доступа к файлам;
+/// память, количество инструкций и время на вызов ограничены.
+pub struct LuaPolicy {
+    sandbox: Sandbox,
+}
+
+impl LuaPolicy {
+    pub fn new(source: &str, name: &str) -> Result<Self> {
+        Self::with_limits(source, name, Limits::policy())
+    }
+
+    pub fn with_limits(source: &str, name: &str, limits: Limits) -> Result<Self> {
+        let sandbox = Sandbox::new(limits)?;
+
+        sandbox.reset_budget();
+        sandbox.lua.load(source).set_name(name).exec()?;
+
+        Ok(Self { sandbox })
+    }
+
+    pub fn from_file(path: &str) -> Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Self::new(&source, path)
+    }
+
+    #[inline]
+    pub fn has_hook(&self, name: &str) -> bool {
+        self.sandbox.lua.globals().get::<Function>(name).is_ok()
+    }
+
+    /// Вызывает хук `name` с аргументами, сериализованными в таблицы Lua.
+    /// Если хук не объявлен, возвращает решение по умолчанию.
+    pub fn call_hook<A: Serialize, C: Serialize>(
+        &self,
+        name: &str,
+        arg: &A,
+        ctx: &C,
+    ) -> Result<HookDecision> {
+        let lua = &self.sandbox.lua;
+
+        let Ok(hook) = lua.globals().get::<Function>(name) else {
+            return Ok(HookDecision::default());
+        };
+
+        self.sandbox.reset_budget();
+
+        let value = hook.call::<Value>((lua.to_value(arg)?, lua.to_value(ctx)?))?;
+
+        if value.is_nil() {
+            return Ok(HookDecision::default());
+        }
+
+        let decision = lua.from_value::<HookDecision>(value).map_err(|e| {
+            Error::Lua(mlua::Error::runtime(format!(
+                "{name}: invalid decision: {e}"
+            )))
+        })?;
+
+        if decision.action == HookAction::Rewrite
+            && decision.text.as_deref().is_none_or(str::is_empty)
+        {
+            return Err(Error::MissingRequiredField(format!("{name}: rewrite text")));
+        }
+
+        Ok(decision)
+    }
+}
+
+/// Окружение задачи автоматизации.
+#[derive(Clone)]
+pub struct JobEnv {
+    /// Магазин, к которому относятся `seller` и `db`.
+    pub shop: Shop,
+    pub router: Arc<ModelRouter>,
+    /// Модель по умолчанию для `ai.chat`.
+    pub model: String,
+    /// Каталог шаблонов для `templates.render`.
+    pub templates_dir: String,
+}
+
+/// Параметры `ai.chat`.
+#[derive(Debug, Default, Deserialize)]
+struct ChatOptions {
+    model: Option<String>,
+    /// Задача, резервные модели которой используются: `summary` (по умолчанию),
+    /// `question` или `review`.
+    task: Option<String>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+}
+
+/// Задача автоматизации на Lua.
+///
+/// Скрипту доступны глобальные таблицы:
+/// - `args` — аргументы запуска (строки),
+/// - `seller` — `place`, `symbol`, `products()`, `product_info(id)`,
+///   `content_hash(info)` — хэш данных товара, полученных `product_info`,
+///   `answer_question(id, text, product_id)`, `answer_review(id, text)`,
+/// - `ai` — `model` — модель окружения задачи, используемая по умолчанию,
+///   `chat(messages, opts)`, где `messages` — список `{ role, content }`
+///   (`role` — `system`, `user`, `assistant` или `tool`),
+///   а `opts` — `{ model, task, temperature, max_tokens }`; возвращает текст ответа
+///   без рассуждений `<think>` и модель, которая его сформировала,
+/// - `db` — `get_summary(product_id)`, `set_summary(product_id, text, source)`,
+///   `is_summary_fresh(product_id, source)`, где `source` — исходные данные сводки
+///   `{ content_hash, template, model }`,
+/// - `templates` — `render(name, ctx)` для шаблонов из `templates_dir`,
+/// - `sleep(secs)` — пауза не дольше оставшегося лимита времени запуска.
+///
+/// Функции `seller`, `ai` и `db` асинхронные: скрипт выполняется на runtime tokio
+/// и не блокирует его во время запросов.
+pub struct LuaJob {
+    sandbox: Sandbox,
+    source: String,
+    name: String,
+}
+
+impl LuaJob {
+    pub fn new(source: &str, name: &str, env: JobEnv, limits: Limits) -> Result<Self> {
+        let sandbox = Sandbox::new(limits)?;
+
+        register_job_api(&sandbox, env)?;
+
+        Ok(Self {
+            sandbox,
+            source: source.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    pub fn from_file(path: &str, env: JobEnv) -> Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Self::new(&source, path, env, Limits::job())
+    }
+
+    /// Выполняет скрипт с аргументами `args`.
+    pub async fn run(&self, args: &HashMap<String, String>) -> Result<()> {
+        let lua = &self.sandbox.lua;
+
+        lua.globals().set("args", lua.to_value(args)?)?;
+
+        self.sandbox.reset_budget();
+
+        let exec = lua.load(&self.source).set_name(&self.name).exec_async();
+
+        match self.sandbox.limits.time {
+            Some(time) => tokio::time::timeout(time, exec)
+                .await
+                .map_err(|_| mlua::Error::runtime("time limit exceeded"))??,
+            None => exec.await?,
+        }
+
+        Ok(())
+    }
+}
+
+fn register_job_api(sandbox: &Sandbox, env: JobEnv) -> Result<()> {
+    let lua = &sandbox.lua;
+    let globals = lua.globals();
+
+    let seller = lua.create_table()?;
+    seller.set("place", env.shop.scli.str_full_symbol())?;
+    seller.set("symbol", env.shop.scli.str_symbol())?;
+    seller.set("shop", env.shop.id.as_str())?;
+
+    let scli = env.shop.scli.clone();
+    seller.set(
+        "products",
+        lua.create_async_function(move |lua, ()| {
+            let scli = scli.clone();
+            async move {
+                let mut rx = scli.all_products_stream();
+                let mut products = Vec::new();
+                while let Some(res) = rx.recv().await {
+                    products.push(res.map_err(mlua::Error::external)?);
+                }
+                lua.to_value(&products)
+            }
+        })?,
+    )?;
+
+    let shop = env.shop.clone();
+    seller.set(
+        "product_info",
+        lua.create_async_function(move |lua, id: String| {
+            let shop = shop.clone();
+            async move {
+                let info = shop
+                    .scli
+                    .get_product_format_info(&id)
+                    .await
+                    .map_err(mlua::Error::external)?;
+                db::upsert_product(&shop.id, &info)
+                    .await
+                    .map_err(mlua::Error::external)?;
+                lua.to_value(&info)
+            }
+        })?,
+    )?;
+
+    let scli = env.shop.scli.clone();
+    seller.set(
+        "answer_question",
+        lua.create_async_function(
+            move |_, (id, text, product_id): (String, String, Option<String>)| {
+                let scli = scli.clone();
+                async move {
+                    scli.answer_question(&id, &text, product_id.as_deref())
+                        .await
+                        .map_err(mlua::Error::external)
+                }
+            },
+        )?,
+    )?;
+
+    let scli = env.shop.scli.clone();
+    seller.set(
+        "answer_review",
+        lua.create_async_function(move |_, (id, text): (String, String)| {
+            let scli = scli.clone();
+            async move {
+                scli.answer_review(&id, &text)
+                    .await
+                    .map_err(mlua::Error::external)
+            }
+        })?,
+    )?;
+
+    seller.set(
+        "content_hash",
+        lua.create_function(|lua, info: Value| {
+            Ok(lua.from_value::<ProductFormatInfo>(info)?.content_hash())
+        })?,
+    )?;
+
+    globals.set("seller", seller)?;
+
+    let ai = lua.create_table()?;
+    ai.set("model", env.model.as_str())?;
+
+    let (router, default_model, shop_id) =
+        (env.router.clone(), env.model.clone(), env.shop.id.clone());
+    ai.set(
+        "chat",
+        lua.create_async_function(move |lua, (messages, opts): (Value, Option<Value>)| {
+            let (router, default_model, shop_id) =
+                (router.clone(), default_model.clone(), shop_id.clone());
+            async move {
+                let messages = lua.from_value::<Vec<Message>>(messages)?;
+                let opts = match opts {
+                    Some(v) => lua.from_value::<ChatOptions>(v)?,
+                    None => ChatOptions::default(),
+                };
+
+                let req = ChatRequest {
+                    model: opts.model.unwrap_or(default_model),
+                    messages,
+                    temperature: opts.temperature,
+                    max_tokens: opts.max_tokens,
+                    ..Default::default()
+                };
+
+                let task = match opts.task.as_deref() {
+                    Some(v) => Task::parse(v).ok_or_else(|| {
+                        mlua::Error::external(format!("ai.chat: unknown task \"{v}\""))
+                    })?,
+                    None => Task::Summary,
+                };
+
+                let mut res = router
+                    .chat(&shop_id, task, &req)
+                    .await
+                    .map_err(mlua::Error::external)?;
+                let text = res
+                    .response
+                    .take_message(0)
+                    .map(|m| genai::strip_reasoning(&m.content).trim().to_string())
+                    .unwrap_or_default();
+
+                Ok((text, res.model))
+            }
+        })?,
+    )?;
+
+    globals.set("ai", ai)?;
+
+    let dbt = lua.create_table()?;
+
+    let shop = env.shop.clone();
+    dbt.set(
+        "get_summary",
+        lua.create_async_function(move |_, product_id: String| {
+            let shop = shop.clone();
+            async move {
+                db::select_product_ai_summary(&shop.key(&product_id))
+                    .await
+                    .map(|row| row.map(|r| r.ai_summary))
+                    .map_err(mlua::Error::external)
+            }
+        })?,
+    )?;
+
+    let shop = env.shop.clone();
+    dbt.set(
+        "set_summary",
+        lua.create_async_function(
+            move |lua, (product_id, text, source): (String, String, Option<Value>)| {
+                let shop = shop.clone();
+                async move {
+                    let source = source
+                        .map(|v| lua.from_value::<SummarySource>(v))
+                        .transpose()?;
+                    db::insert_or_replace_product_ai_summary(
+                        &shop.key(&product_id),
+                        &text,
+                        source.as_ref(),
+                    )
+                    .await
+                    .map_err(mlua::Error::external)
+                }
+            },
+        )?,
+    )?;
+
+    let shop = env.shop.clone();
+    dbt.set(
+        "is_summary_fresh",
+        lua.create_async_function(move |lua, (product_id, source): (String, Value)| {
+            let shop = shop.clone();
+            async move {
+                let source = lua.from_value::<SummarySource>(source)?;
+                let row = db::select_product_ai_summary(&shop.key(&product_id))
+                    .await
+                    .map_err(mlua::Error::external)?;
+                Ok(row.is_some_and(|r| r.is_fresh(&source)))
+            }
+        })?,
+    )?;
+
+    globals.set("db", dbt)?;
+
+    let templates = lua.create_table()?;
+
+    let templates_dir = env.templates_dir.clone();
+    templates.set(
+        "render",
+        lua.create_function(move |lua, (name, ctx): (String, Value)| {
+            // Только относительный путь внутри каталога шаблонов: абсолютный путь
+            // заменил бы каталог при `join`.
+            let path = Path::new(&name);
+            if name.is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+                return Err(mlua::Error::runtime("invalid template name"));
+            }
+
+            let template = std::fs::read_to_string(Path::new(&templates_dir).join(&name))
+                .map_err(mlua::Error::external)?;
+            let ctx = Context::from_value(lua.from_value::<serde_json::Value>(ctx)?)
+                .map_err(mlua::Error::external)?;
+
+            Tera::one_off(&template, &ctx, false).map_err(mlua::Error::external)
+        })?,
+    )?;
+
+    globals.set("templates", templates)?;
+
+    let budget = sandbox.budget.clone();
+    globals.set(
+        "sleep",
+        lua.create_async_function(move |_, secs: f64| {
+            let deadline = budget.lock().unwrap().deadline;
+            async move {
+                let mut duration = Duration::try_from_secs_f64(secs.max(0.0))
+                    .map_err(|_| mlua::Error::runtime("invalid sleep duration"))?;
+                if let Some(deadline) = deadline {
+                    duration = duration.min(deadline.saturating_duration_since(Instant::now()));
+                }
+                tokio::time::sleep(duration).await;
+                Ok(())
+            }
+        })?,
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn lua_policy_test() {
+    let policy = LuaPolicy::new(
+        r#"
+function on_review(r, ctx)
+    if r.score <= 2 then
+        return { action = "escalate", reason = "low score" }
+    end
+    if r.text == "" then
+        return { action = "rewrite", text = "Спасибо за оценку!" }
+    end
+    return { model = "local-model" }
+end
+
+function spin()
+    while true do end
+end
+"#,
+        "test",
+    )
+    .unwrap();
+
+    #[derive(Serialize)]
+    struct Review<'a> {
+        score: f32,
+        text: &'a str,
+    }
+
+    let decision = policy
+        .call_hook(
+            "on_review",
+            &Review {
+                score: 1.0,
+                text: "",
+            },
+            &(),
+        )
+        .unwrap();
+    assert_eq!(decision.action, HookAction::Escalate);
+
+    let decision = policy
+        .call_hook(
+            "on_review",
+            &Review {
+                score: 5.0,
+                text: "",
+            },
+            &(),
+        )
+        .unwrap();
+    assert_eq!(decision.action, HookAction::Rewrite);
+
+    let decision = policy
+        .call_hook(
+            "on_review",
+            &Review {
+                score: 5.0,
+                text: "Хорошо",
+            },
+            &(),
+        )
+        .unwrap();
+    assert_eq!(decision.action, HookAction::Continue);
+    assert_eq!(decision.model.as_deref(), Some("local-model"));
+
+    let decision = policy.call_hook("on_question", &(), &()).unwrap();
+    assert_eq!(decision.action, HookAction::Continue);
+
+    assert!(policy.call_hook("spin", &(), &()).is_err());
+    assert!(LuaPolicy::new("loadfile('/etc/passwd')", "test").is_err());
+    assert!(LuaPolicy::from_file("scripts/policy.lua").is_ok());
+}
+
+#[tokio::test]
+async fn lua_job_test() {
+    use crate::{
+        genai::{AiProvider, MockBackend},
+        sellerapi::WbSellerClient,
+    };
+
+    let env = JobEnv {
+        shop: Shop {
+            id: "wb-2".into(),
+            scli: SellerClient::Wb(Arc::new(WbSellerClient::new(String::new()))),
+            model: "test".into(),
+            question_template: "templates/question.j2".into(),
+            review_template: "templates/review.j2".into(),
+            policy: None,
+            auto_approve: Vec::new(),
+        },
+        router: Arc::new(ModelRouter::new(
+            AiProvider::Mock(MockBackend::new(Some("A!".into()))),
+            Default::default(),
+            Duration::from_secs(60),
+        )),
+        model: "test".into(),
+        templates_dir: "templates".into(),
+    };
+
+    let job = LuaJob::new(
+        r#"
+assert(seller.symbol == "wb")
+assert(seller.shop == "wb-2")
+local prompt = templates.render("question.j2", { place = seller.place, ai_summary = "S", question = args.q })
+assert(string.find(prompt, "Wildberries", 1, true))
+assert(string.find(prompt, "Q?", 1, true))
+assert(not pcall(templates.render, "../Cargo.toml", {}))
+assert(not pcall(templates.render, "/etc/passwd", {}))
+assert(not pcall(templates.render, "sub/../../Cargo.toml", {}))
+local answer, model = ai.chat({ { role = "system", content = "S" }, { role = "user", content = prompt } })
+assert(answer == "A!" and model == "test")
+assert(not pcall(ai.chat, { { role = "user", content = "?" } }, { task = "unknown" }))
+assert(not pcall(ai.chat, { { role = "robot", content = "?" } }))
+sleep(0)
+assert(not pcall(sleep, math.huge))
+"#,
+        "test",
+        env.clone(),
+        Limits::job(),
+    )
+    .unwrap();
+
+    job.run(&HashMap::from([("q".to_string(), "Q?".to_string())]))
+        .await
+        .unwrap();
+
+    let limits = Limits {
+        instructions: 100_000,
+        ..Limits::job()
+    };
+    let job = LuaJob::new("while true do end", "test", env, limits).unwrap();
+    assert!(job.run(&HashMap::new()).await.is_err());
+}
+
+#[tokio::test]
+async fn resummarize_job_test() {
+    use crate::{
+        genai::{AiProvider, MockBackend},
+        sellerapi::fake::{self, FakeMarketplace},
+    };
+
+    let server = FakeMarketplace::start().await;
+    server.with(|d| {
+        for id in [1, 2] {
+            d.ozon_products.push(fake::ozon_product(
+                id,
+                2000 + id,
+                &format!("Товар {id}"),
+                "990",
+            ));
+            d.ozon_descriptions.insert(id, "Описание".into());
+            d.ozon_attributes
+                .push(fake::ozon_attributes(id, 2000 + id, &[(1, "Синий")]));
+        }
+        d.ozon_category_attributes
+            .push(fake::ozon_category_attribute(1, "Цвет"));
+    });
+
+    let shop = Shop {
+        id: "oz-resummarize".into(),
+        scli: server.ozon_seller(),
+        model: "test".into(),
+        question_template: "templates/question.j2".into(),
+        review_template: "templates/review.j2".into(),
+        policy: None,
+        auto_approve: Vec::new(),
+    };
+
+    let run = |reply: &str, model: &str| {
+        let env = JobEnv {
+            shop: shop.clone(),
+            router: Arc::new(ModelRouter::new(
+                AiProvider::Mock(MockBackend::new(Some(reply.into()))),
+                Default::default(),
+                Duration::from_secs(60),
+            )),
+            model: model.into(),
+            templates_dir: "templates".into(),
+        };
+        let args = HashMap::from([("template".to_string(), "product_summary.j2".to_string())]);
+
+        async move {
+            LuaJob::from_file("scripts/resummarize.lua", env)
+                .unwrap()
+                .run(&args)
+                .await
+                .unwrap();
+        }
+    };
+    let summary = |id: &str| {
+        let key = shop.key(id);
+        async move { db::select_product_ai_summary(&key).await.unwrap().unwrap() }
+    };
+
+    run("A", "test").await;
+    assert_eq!(summary("2001").await.ai_summary, "A");
+    assert_eq!(summary("2002").await.ai_summary, "A");
+
+    // Данные товаров не изменились: сводки не формируются повторно.
+    run("B", "test").await;
+    assert_eq!(summary("2001").await.ai_summary, "A");
+
+    server.with(|d| d.ozon_descriptions.insert(2, "Новое описание".into()));
+    run("C", "test").await;
+    assert_eq!(summary("2001").await.ai_summary, "A");
+
+    let row = summary("2002").await;
+    assert_eq!(row.ai_summary, "C");
+    let source = row.source.unwrap();
+    assert_eq!(source.template, "product_summary.j2");
+    assert_eq!(source.model, "test");
+
+    // Сменилась модель окружения задачи: сводки формируются заново.
+    run("D", "other").await;
+    for id in ["2001", "2002"] {
+        let row = summary(id).await;
+        assert_eq!(row.ai_summary, "D");
+        assert_eq!(row.source.unwrap().model, "other");
+    }
+}
@@ -1 +1,1290 @@
-struct Params {}
+use super::{
+    dotlua::{HookAction, LuaPolicy},
+    tools::ProductTools,
+};
+use crate::{
+    db::{self, FeedbackRow, FeedbackStatus},
+    error::{Error, Result},
+    genai::{self, ChatStreamEvent, Conversation, ModelRouter, StructuredOutput, Task},
+    sellerapi::{
+        ObserverCursor, RetryPolicy, SellerClient, UnansweredFilter,
+        abcmodels::{NewFeedback, ProductFormatInfo},
+    },
+    shop::Shop,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{fmt::Write, sync::Arc, time::Duration};
+use tera::{Context, Tera};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Пауза перед перезапуском наблюдателя после ошибки.
+const OBSERVER_RESTART_DELAY: Duration = Duration::from_secs(5);
+
+/// Описание формата структурированного ответа, добавляемое к системному промпту.
+const STRUCTURED_INSTRUCTIONS: &str = "Верни ответ в формате JSON объекта с полями:
+- answer: текст ответа покупателю;
+- confidence: уверенность в ответе от 0 до 1, низкая, если в данных о товаре нет нужных сведений;
+- escalate: true, если ответ должен подготовить человек (брак, возврат, претензия, вопрос вне данных о товаре);
+- reason: причина передачи человеку или null;
+- sentiment: тональность обратной связи: positive, neutral или negative.";
+
+/// Инструкция, добавляемая к системному промпту при использовании инструментов.
+const TOOLS_INSTRUCTIONS: &str = "Сведения о товаре (описание, характеристики, цену, наличие) и ранее одобренные ответы магазина получай с помощью инструментов. Не выдумывай сведения, которых нет в результатах инструментов.";
+
+/// Правило автоматического одобрения черновика ответа.
+/// Правило срабатывает, если обратная связь удовлетворяет всем заданным условиям.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoApproveRule {
+    /// Тип обратной связи: `"question"` или `"review"`. `None` — любой.
+    pub kind: Option<String>,
+    /// Минимальная оценка отзыва (включительно).
+    pub min_score: Option<f32>,
+    /// Максимальная оценка отзыва (включительно).
+    pub max_score: Option<f32>,
+    /// Только обратная связь без текста.
+    pub empty_text: bool,
+}
+
+impl AutoApproveRule {
+    /// Отзывы с оценкой 5 без текста.
+    pub fn five_star_without_text() -> Self {
+        Self {
+            kind: Some("review".into()),
+            min_score: Some(5.0),
+            empty_text: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn matches(&self, feedback: &NewFeedback) -> bool {
+        if self.kind.as_deref().is_some_and(|k| k != feedback.kind()) {
+            return false;
+        }
+
+        let (text, score) = match feedback {
+            NewFeedback::Question(q) => (q.text.as_str(), None),
+            NewFeedback::Review(r) => (r.text.as_str(), Some(r.score)),
+        };
+
+        if self.empty_text && !text.trim().is_empty() {
+            return false;
+        }
+
+        if let Some(min) = self.min_score
+            && score.is_none_or(|s| s < min)
+        {
+            return false;
+        }
+
+        if let Some(max) = self.max_score
+            && score.is_none_or(|s| s > max)
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Параметры обработчика обратной связи.
+#[derive(Debug, Clone)]
+pub struct Params {
+    /// Путь к шаблону промпта для ответа на вопрос.
+    pub question_template: String,
+    /// Путь к шаблону промпта для ответа на отзыв.
+    pub review_template: String,
+    /// Модель AI провайдера.
+    pub model: String,
+    /// Интервал опроса новых вопросов.
+    pub question_interval: Duration,
+    /// Интервал опроса новых отзывов.
+    pub review_interval: Duration,
+    /// Максимальное количество попыток обработки одной записи.
+    pub max_attempts: u32,
+    /// Публиковать ответы только после одобрения человеком.
+    pub require_approval: bool,
+    /// Правила, по которым черновик одобряется без участия человека.
+    pub auto_approve: Vec<AutoApproveRule>,
+    /// Интервал проверки одобренных ответов, ожидающих публикации.
+    pub approved_poll_interval: Duration,
+    /// Путь к Lua скрипту политики обработки (см. `LuaPolicy`).
+    pub policy_script: Option<String>,
+    /// При запуске обработать все вопросы и отзывы, ожидающие ответа,
+    /// а не только полученные после сохранённой позиции наблюдателя.
+    pub process_backlog: bool,
+    /// Повтор запросов к маркетплейсу после ошибок.
+    pub retry: RetryPolicy,
+    /// Количество последних одобренных ответов магазина на обратную связь того же типа,
+    /// добавляемых к промпту в качестве примеров (few-shot).
+    pub few_shot: u32,
+    /// Запрашивать ответ в формате JSON (см. `StructuredAnswer`).
+    pub structured: bool,
+    /// Структурированный ответ с меньшей уверенностью передаётся человеку.
+    pub min_confidence: f32,
+    /// Модель получает данные товара с помощью инструментов (см. `ProductTools`),
+    /// а в промпт передаётся только сохранённая AI-сводка.
+    pub tools: bool,
+    /// Максимальное количество запросов к модели с вызовами инструментов.
+    pub max_tool_steps: u32,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            question_template: "templates/question.j2".into(),
+            review_template: "templates/review.j2".into(),
+            model: "deepseek/deepseek-r1-0528:free".into(),
+            question_interval: Duration::from_secs(11),
+            review_interval: Duration::from_secs(7),
+            max_attempts: 3,
+            require_approval: true,
+            auto_approve: Vec::new(),
+            approved_poll_interval: Duration::from_secs(5),
+            policy_script: None,
+            process_backlog: false,
+            retry: RetryPolicy::default(),
+            few_shot: 3,
+            structured: false,
+            min_confidence: 0.5,
+            tools: false,
+            max_tool_steps: 4,
+        }
+    }
+}
+
+/// Обработчик обратной связи: получает новые вопросы и отзывы от наблюдателя,
+/// генерирует ответ через AI провайдера и публикует его на маркетплейсе.
+///
+/// Результат каждого этапа сохраняется в таблице `feedback`, поэтому после
+/// перезапуска обработка продолжается с места остановки: уже опубликованные
+/// ответы не дублируются, а сгенерированные не генерируются повторно.
+///
+/// Позиция наблюдателя сохраняется в одной транзакции с новой записью, поэтому
+/// каждая обратная связь принимается в обработку ровно один раз, а полученная
+/// во время остановки — после перезапуска. При первом запуске обратная связь,
+/// ожидающая ответа, пропускается, если не включено `process_backlog`
+/// (см. `FeedbackController::process_backlog`).
+///
+/// Если включено `require_approval`, сгенерированный ответ остаётся черновиком
+/// (`generated`) до одобрения через веб-интерфейс или по правилу `auto_approve`.
+///
+/// Lua политика (`policy_script`) может пропустить обратную связь, подставить
+/// готовый ответ, выбрать шаблон и модель или передать её человеку (`escalated`).
+/// Хук `before_publish` вызывается перед каждой публикацией для окончательного
+/// текста ответа, в том числе одобренного или отредактированного человеком.
+pub struct FeedbackController {
+    shop: Shop,
+    router: Arc<ModelRouter>,
+    params: Params,
+    question_template: String,
+    review_template: String,
+    policy: Option<LuaPolicy>,
+}
+
+/// Данные товара, доступные шаблонам промптов и хукам политики.
+#[derive(Debug, Serialize)]
+pub struct ProductContext {
+    /// Название маркетплейса.
+    pub place: &'static str,
+    /// AI-сводка о товаре или текстовое представление его данных.
+    pub ai_summary: String,
+    /// Данные товара, если они запрашивались у маркетплейса.
+    pub product: Option<ProductFormatInfo>,
+}
+
+impl ProductContext {
+    /// Формирует данные товара для шаблона и политики.
+    /// Если сохранённой AI-сводки нет, данные товара запрашиваются у маркетплейса.
+    pub async fn load(shop: &Shop, product_id: &str) -> Result<Self> {
+        let scli = &shop.scli;
+        let summary_id = shop.key(product_id);
+
+        let ctx = match db::select_product_ai_summary(&summary_id).await? {
+            Some(row) => Self {
+                place: scli.str_full_symbol(),
+                ai_summary: row.ai_summary,
+                product: None,
+            },
+            None => {
+                let product = scli.get_product_format_info(product_id).await?;
+                db::upsert_product(&shop.id, &product).await?;
+                Self {
+                    place: scli.str_full_symbol(),
+                    ai_summary: format_product_info(&product),
+                    product: Some(product),
+                }
+            }
+        };
+
+        Ok(ctx)
+    }
+
+    /// Формирует данные товара без запросов к маркетплейсу: сохранённая AI-сводка
+    /// или пустая строка, если её нет.
+    pub async fn load_cached(shop: &Shop, product_id: &str) -> Result<Self> {
+        let ai_summary = db::select_product_ai_summary(&shop.key(product_id))
+            .await?
+            .map(|row| row.ai_summary)
+            .unwrap_or_default();
+
+        Ok(Self {
+            place: shop.scli.str_full_symbol(),
+            ai_summary,
+            product: None,
+        })
+    }
+}
+
+/// Ответ на обратную связь и модель, которая его сформировала.
+#[derive(Debug, Default)]
+pub struct Answer {
+    pub text: String,
+    /// `None`, если ответ задан политикой.
+    pub model: Option<String>,
+    /// Тональность обратной связи по оценке модели (структурированный ответ).
+    pub sentiment: Option<Sentiment>,
+    /// Уверенность модели в ответе (структурированный ответ).
+    pub confidence: Option<f32>,
+    /// Причина, по которой ответ должен проверить человек.
+    pub escalate: Option<String>,
+}
+
+/// Тональность обратной связи.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sentiment {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+impl Sentiment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Sentiment::Positive => "positive",
+            Sentiment::Neutral => "neutral",
+            Sentiment::Negative => "negative",
+        }
+    }
+}
+
+/// Структурированный ответ модели на обратную связь (`Params::structured`).
+#[derive(Debug, Deserialize)]
+pub struct StructuredAnswer {
+    /// Текст ответа покупателю.
+    pub answer: String,
+    /// Уверенность модели в ответе от 0 до 1.
+    pub confidence: f32,
+    /// Ответ должен подготовить человек.
+    pub escalate: bool,
+    /// Причина передачи человеку.
+    #[serde(default)]
+    pub reason: Option<String>,
+    pub sentiment: Sentiment,
+}
+
+impl StructuredOutput for StructuredAnswer {
+    const NAME: &'static str = "feedback_answer";
+
+    fn schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "answer": { "type": "string" },
+                "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                "escalate": { "type": "boolean" },
+                "reason": { "type": ["string", "null"] },
+                "sentiment": { "type": "string", "enum": ["positive", "neutral", "negative"] }
+            },
+            "required": ["answer", "confidence", "escalate", "reason", "sentiment"],
+            "additionalProperties": false
+        })
+    }
+}
+
+/// Аргумент хука `before_publish`.
+#[derive(Serialize)]
+struct PublishHookArg<'a> {
+    text: &'a str,
+    kind: &'static str,
+    feedback: &'a NewFeedback,
+}
+
+impl FeedbackController {
+    pub fn new(shop: Shop, router: Arc<ModelRouter>, params: Params) -> Result<Self> {
+        let question_template = std::fs::read_to_string(&params.question_template)?;
+        let review_template = std::fs::read_to_string(&params.review_template)?;
+        let policy = params
+            .policy_script
+            .as_deref()
+            .map(LuaPolicy::from_file)
+            .transpose()?;
+
+        Ok(Self {
+            shop,
+            router,
+            params,
+            question_template,
+            review_template,
+            policy,
+        })
+    }
+
+    /// Ключ записи в базе данных: `"{shop_id}/{feedback_id}"`.
+    #[inline]
+    fn feedback_key(&self, feedback_id: &str) -> String {
+        self.shop.key(feedback_id)
+    }
+
+    /// Проверяет, доступны ли клиенту методы работы с вопросами и отзывами.
+    /// Для Ozon они доступны только с подпиской Premium Plus.
+    pub async fn is_feedback_available(&self) -> Result<bool> {
+        match self.shop.scli {
+            SellerClient::Ozon(ref cli) => Ok(cli.seller_rating_summary().await?.premium_plus),
+            SellerClient::Wb(_) => Ok(true),
+        }
+    }
+
+    /// Запускает бесконечный цикл обработки: сначала дообрабатывает
+    /// незавершённые записи, затем слушает наблюдатель новой обратной связи
+    /// и периодически публикует одобренные ответы.
+    pub async fn run(&self) -> Result<()> {
+        if !self.is_feedback_available().await? {
+            println!(
+                "Магазин {}: методы работы с вопросами и отзывами доступны только для продавцов с подпиской Premium Plus.",
+                self.shop.id
+            );
+            return Ok(());
+        }
+
+        self.resume(&[
+            FeedbackStatus::Received,
+            FeedbackStatus::Generated,
+            FeedbackStatus::Approved,
+        ])
+        .await?;
+
+        let mut approved_ticker = tokio::time::interval(self.params.approved_poll_interval);
+
+        if self.params.process_backlog {
+            // Позиции создаются до выгрузки, чтобы обратная связь, полученная во время
+            // выгрузки, не была пропущена наблюдателем.
+            self.load_cursor("question").await?;
+            self.load_cursor("review").await?;
+
+            if let Err(e) = self
+                .process_backlog(&UnansweredFilter::default(), None)
+                .await
+            {
+                eprintln!(
+                    "Ошибка обработки обратной связи магазина {}, ожидающей ответа: {e}",
+                    self.shop.id
+                );
+            }
+        }
+
+        loop {
+            println!(
+                "Запуск обработчика обратной связи магазина {}...",
+                self.shop.id
+            );
+
+            let question_cursor = self.load_cursor("question").await?;
+            let review_cursor = self.load_cursor("review").await?;
+
+            let mut rx = self.shop.scli.spawn_new_feedback_observer(
+                self.params.question_interval,
+                self.params.review_interval,
+                question_cursor,
+                review_cursor,
+                self.params.retry,
+            );
+
+            loop {
+                tokio::select! {
+                    res = rx.recv() => match res {
+                        Some(Ok(feedback)) => {
+                            if let Err(e) = self.process(feedback).await {
+                                eprintln!("Ошибка обработки обратной связи: {e}");
+                            }
+                        }
+                        Some(Err(e)) => {
+                            let kind = if e.is_transient() { "временная" } else { "постоянная" };
+                            eprintln!(
+                                "Ошибка получения новой обратной связи магазина {} ({kind}): {e}",
+                                self.shop.id
+                            );
+                        }
+                        None => break,
+                    },
+                    _ = approved_ticker.tick() => {
+                        if let Err(e) = self.resume(&[FeedbackStatus::Approved]).await {
+                            eprintln!("Ошибка публикации одобренных ответов: {e}");
+                        }
+                    }
+                }
+            }
+
+            tokio::time::sleep(OBSERVER_RESTART_DELAY).await;
+        }
+    }
+
+    /// Загружает сохранённую позицию наблюдателя за обратной связью типа `kind`.
+    /// Если позиции нет, наблюдатель начинает с текущего момента.
+    async fn load_cursor(&self, kind: &str) -> Result<ObserverCursor> {
+        let id = self.shop.key(kind);
+
+        if let Some(row) = db::select_observer_cursor(&id).await? {
+            return Ok(ObserverCursor {
+                last_seen: row.last_seen,
+                seen_ids: row.seen_ids.into_iter().collect(),
+            });
+        }
+
+        let now = time::UtcDateTime::now().unix_timestamp().max(0) as u64;
+        db::insert_observer_cursor_if_absent(&id, now).await?;
+
+        Ok(ObserverCursor {
+            last_seen: now,
+            ..Default::default()
+        })
+    }
+
+    /// Обрабатывает вопросы и отзывы, ожидающие ответа на маркетплейсе, с учётом фильтра
+    /// `filter`, но не более `limit` новых записей. Уже известная обратная связь пропускается.
+    /// Ошибки выгрузки страниц выводятся в лог и не прерывают обработку остальных записей.
+    /// Возвращает количество принятых в обработку записей.
+    pub async fn process_backlog(
+        &self,
+        filter: &UnansweredFilter,
+        limit: Option<usize>,
+    ) -> Result<usize> {
+        let mut rx = self
+            .shop
+            .scli
+            .unanswered_feedback_stream(filter.clone(), self.params.retry);
+        let mut count = 0;
+
+        while let Some(res) = rx.recv().await {
+            if limit.is_some_and(|limit| count >= limit) {
+                break;
+            }
+
+            let feedback = match res {
+                Ok(feedback) => feedback,
+                Err(e) => {
+                    let kind = if e.is_transient() {
+                        "временная"
+                    } else {
+                        "постоянная"
+                    };
+                    eprintln!(
+                        "Ошибка получения обратной связи магазина {}, ожидающей ответа ({kind}): {e}",
+                        self.shop.id
+                    );
+                    continue;
+                }
+            };
+            let key = self.feedback_key(feedback.id());
+
+            if !db::insert_feedback_if_absent(
+                &key,
+                feedback.kind(),
+                &serde_json::to_string(&feedback)?,
+            )
+            .await?
+            {
+                continue;
+            }
+            count += 1;
+
+            let row = db::select_feedback(&key)
+                .await?
+                .ok_or_else(|| Error::MissingRequiredField(format!("feedback {key}")))?;
+
+            if let Err(e) = self.process_row(&feedback, row).await {
+                eprintln!("Ошибка обработки обратной связи: {e}");
+            }
+        }
+
+        println!(
+            "Магазин {}: принято в обработку {count} записей, ожидающих ответа.",
+            self.shop.id
+        );
+
+        Ok(count)
+    }
+
+    /// Дообрабатывает записи в статусах `statuses`: прерванные перезапуском или
+    /// ошибкой, а также одобренные после проверки.
+    pub async fn resume(&self, statuses: &[FeedbackStatus]) -> Result<()> {
+        let prefix = self.shop.key("");
+
+        for row in
+            db::select_unfinished_feedback(&prefix, statuses, self.params.max_attempts).await?
+        {
+            let feedback = match serde_json::from_str::<NewFeedback>(&row.payload) {
+                Ok(v) => v,
+                Err(e) => {
+                    db::set_feedback_failed(&row.id, &format!("invalid payload: {e}")).await?;
+                    continue;
+                }
+            };
+
+            if let Err(e) = self.process_row(&feedback, row).await {
+                eprintln!("Ошибка обработки обратной связи: {e}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Принимает обратную связь от наблюдателя и возвращает её статус
+    /// после выполнения всех доступных этапов.
+    /// Полученная ранее обратная связь повторно не обрабатывается:
+    /// незавершённые записи дообрабатывает `resume`.
+    pub async fn process(&self, feedback: NewFeedback) -> Result<FeedbackStatus> {
+        let key = self.feedback_key(feedback.id());
+
+        let inserted = db::insert_observed_feedback(
+            &self.shop.key(feedback.kind()),
+            feedback.id(),
+            feedback.published_at(),
+            &key,
+            feedback.kind(),
+            &serde_json::to_string(&feedback)?,
+        )
+        .await?;
+
+        let row = db::select_feedback(&key)
+            .await?
+            .ok_or_else(|| Error::MissingRequiredField(format!("feedback {key}")))?;
+
+        if !inserted {
+            return Ok(row.status);
+        }
+
+        self.process_row(&feedback, row).await
+    }
+
+    async fn process_row(
+        &self,
+        feedback: &NewFeedback,
+        row: FeedbackRow,
+    ) -> Result<FeedbackStatus> {
+        let res = self.advance(feedback, &row).await;
+
+        if let Err(ref e) = res {
+            db::set_feedback_failed(&row.id, &e.to_string()).await?;
+        }
+
+        res
+    }
+
+    /// Выполняет оставшиеся этапы, начиная с текущего статуса записи:
+    /// проверку политикой, генерацию черновика, одобрение и публикацию.
+    async fn advance(&self, feedback: &NewFeedback, row: &FeedbackRow) -> Result<FeedbackStatus> {
+        let key = row.id.as_str();
+        let mut status = row.status;
+        let mut answer = row.answer.clone();
+
+        if status == FeedbackStatus::Received {
+            let Some(draft) = self.draft_answer(key, feedback).await? else {
+                return Ok(db::select_feedback(key).await?.map_or(status, |r| r.status));
+            };
+
+            db::set_feedback_generated(key, &draft.text, draft.model.as_deref()).await?;
+            println!(
+                "Черновик ответа на {key} ({}): {}",
+                draft.model.as_deref().unwrap_or("политика"),
+                draft.text
+            );
+
+            status = FeedbackStatus::Generated;
+            answer = Some(draft.text);
+        }
+
+        if status == FeedbackStatus::Generated {
+            if self.params.require_approval && !self.is_auto_approved(feedback) {
+                return Ok(status);
+            }
+
+            db::transition_feedback(key, FeedbackStatus::Generated, FeedbackStatus::Approved)
+                .await?;
+            status = FeedbackStatus::Approved;
+        }
+
+        if status == FeedbackStatus::Approved {
+            let answer = answer.ok_or_else(|| Error::MissingRequiredField("answer".into()))?;
+            let Some(answer) = self.check_before_publish(key, feedback, answer).await? else {
+                return Ok(db::select_feedback(key).await?.map_or(status, |r| r.status));
+            };
+
+            let res = self.publish(feedback, &answer).await;
+            let error = res.as_ref().err().map(|e| e.to_string());
+            db::insert_publish_attempt(key, &answer, error.as_deref()).await?;
+            res?;
+
+            db::transition_feedback(key, FeedbackStatus::Approved, FeedbackStatus::Published)
+                .await?;
+            println!("Ответ на {key} опубликован.");
+
+            status = FeedbackStatus::Published;
+        }
+
+        Ok(status)
+    }
+
+    /// Формирует черновик ответа с учётом решений политики.
+    /// Возвращает `None`, если политика пропустила обратную связь или передала её человеку.
+    async fn draft_answer(&self, key: &str, feedback: &NewFeedback) -> Result<Option<Answer>> {
+        let ctx = self.product_context(feedback.product_id()).await?;
+
+        let decision = match (&self.policy, feedback) {
+            (Some(policy), NewFeedback::Question(q)) => policy.call_hook("on_question", q, &ctx)?,
+            (Some(policy), NewFeedback::Review(r)) => policy.call_hook("on_review", r, &ctx)?,
+            (None, _) => Default::default(),
+        };
+
+        let answer = match decision.action {
+            HookAction::Skip => {
+                db::set_feedback_skipped(key, decision.reason.as_deref()).await?;
+                return Ok(None);
+            }
+            HookAction::Escalate => {
+                db::set_feedback_escalated(key, decision.reason.as_deref().unwrap_or_default())
+                    .await?;
+                return Ok(None);
+            }
+            HookAction::Rewrite => Answer {
+                text: decision.text.unwrap_or_default(),
+                ..Default::default()
+            },
+            HookAction::Continue => {
+                self.generate_answer_with(
+                    feedback,
+                    ctx,
+                    decision.template.as_deref(),
+                    decision.model.as_deref(),
+                )
+                .await?
+            }
+        };
+
+        if let (Some(sentiment), Some(confidence)) = (answer.sentiment, answer.confidence) {
+            db::set_feedback_analysis(key, sentiment.as_str(), confidence).await?;
+        }
+
+        if let Some(ref reason) = answer.escalate {
+            db::set_feedback_generated(key, &answer.text, answer.model.as_deref()).await?;
+            db::set_feedback_escalated(key, reason).await?;
+            println!("Ответ на {key} передан человеку: {reason}");
+            return Ok(None);
+        }
+
+        Ok(Some(answer))
+    }
+
+    /// Проверяет окончательный текст ответа хуком `before_publish` политики.
+    /// Возвращает текст для публикации или `None`, если политика пропустила
+    /// обратную связь или передала её человеку.
+    async fn check_before_publish(
+        &self,
+        key: &str,
+        feedback: &NewFeedback,
+        answer: String,
+    ) -> Result<Option<String>> {
+        let Some(ref policy) = self.policy else {
+            return Ok(Some(answer));
+        };
+
+        let arg = PublishHookArg {
+            text: &answer,
+            kind: feedback.kind(),
+            feedback,
+        };
+
+        let decision = policy.call_hook("before_publish", &arg, &())?;
+
+        match decision.action {
+            HookAction::Skip => {
+                db::set_feedback_skipped(key, decision.reason.as_deref()).await?;
+                Ok(None)
+            }
+            HookAction::Escalate => {
+                let reason = decision.reason.unwrap_or_default();
+                db::set_feedback_escalated(key, &reason).await?;
+                println!("Ответ на {key} передан человеку: {reason}");
+                Ok(None)
+            }
+            HookAction::Rewrite => {
+                let text = decision.text.unwrap_or_default();
+                db::rewrite_approved_feedback(key, &text).await?;
+                Ok(Some(text))
+            }
+            HookAction::Continue => Ok(Some(answer)),
+        }
+    }
+
+    #[inline]
+    fn is_auto_approved(&self, feedback: &NewFeedback) -> bool {
+        self.params.auto_approve.iter().any(|r| r.matches(feedback))
+    }
+
+    /// Генерирует ответ на вопрос или отзыв без публикации.
+    pub async fn generate_answer(&self, feedback: &NewFeedback) -> Result<Answer> {
+        let ctx = self.product_context(feedback.product_id()).await?;
+        self.generate_answer_with(feedback, ctx, None, None).await
+    }
+
+    /// Данные товара для шаблона и политики. Если модель получает данные
+    /// с помощью инструментов, маркетплейс не запрашивается.
+    async fn product_context(&self, product_id: &str) -> Result<ProductContext> {
+        match self.params.tools {
+            true => ProductContext::load_cached(&self.shop, product_id).await,
+            false => ProductContext::load(&self.shop, product_id).await,
+        }
+    }
+
+    /// Генерирует ответ на вопрос или отзыв без публикации в потоковом режиме:
+    /// фрагменты ответа приходят в канал по мере генерации.
+    /// Возвращает модель, формирующую ответ, и канал.
+    pub async fn generate_answer_stream(
+        &self,
+        feedback: &NewFeedback,
+    ) -> Result<(String, UnboundedReceiver<Result<ChatStreamEvent>>)> {
+        let ctx = ProductContext::load(&self.shop, feedback.product_id()).await?;
+        let req = self
+            .conversation(feedback, ctx, None)
+            .await?
+            .into_request(&self.params.model);
+
+        self.router
+            .chat_stream(&self.shop.id, task(feedback), &req)
+            .await
+    }
+
+    /// Генерирует ответ по шаблону `template` (путь к файлу) и модели `model`.
+    /// Если они не заданы, используются значения из `Params`. Если модель недоступна,
+    /// ответ формирует резервная модель задачи (см. `ModelRouter`).
+    ///
+    /// Если включено `Params::tools`, модель может вызывать инструменты `ProductTools`,
+    /// если `Params::structured` — ответ запрашивается в формате JSON (см. `StructuredAnswer`).
+    async fn generate_answer_with(
+        &self,
+        feedback: &NewFeedback,
+        ctx: ProductContext,
+        template: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<Answer> {
+        let mut conv = self.conversation(feedback, ctx, template).await?;
+        if self.params.tools {
+            conv.push_system(TOOLS_INSTRUCTIONS);
+        }
+        if self.params.structured {
+            conv.push_system(STRUCTURED_INSTRUCTIONS);
+        }
+
+        let mut req = conv.into_request(model.unwrap_or(&self.params.model));
+        if self.params.structured {
+            req.response_format = Some(StructuredAnswer::response_format());
+        }
+
+        let (req, mut routed) = match self.params.tools {
+            true => {
+                let tools = ProductTools::new(&self.shop, feedback.product_id());
+                self.router
+                    .chat_with_tools(
+                        &self.shop.id,
+                        task(feedback),
+                        &req,
+                        &tools,
+                        self.params.max_tool_steps,
+                    )
+                    .await?
+            }
+            false => {
+                let routed = self
+                    .router
+                    .chat(&self.shop.id, task(feedback), &req)
+                    .await?;
+                (req, routed)
+            }
+        };
+
+        if self.params.structured {
+            let res = self
+                .router
+                .parse_json_response::<StructuredAnswer>(&self.shop.id, task(feedback), req, routed)
+                .await?;
+            return self.structured_answer(res);
+        }
+
+        let raw = routed
+            .response
+            .take_message(0)
+            .map(|m| m.content)
+            .unwrap_or_default();
+        let text = genai::prepare_answer(&raw, self.shop.scli.answer_max_len())?;
+
+        Ok(Answer {
+            text,
+            model: Some(routed.model),
+            ..Default::default()
+        })
+    }
+
+    /// Ответ по структурированному ответу модели. Ответ передаётся человеку, если модель
+    /// запросила это или её уверенность ниже `Params::min_confidence`;
+    /// в этом случае текст ответа может быть пустым.
+    fn structured_answer(&self, res: genai::Structured<StructuredAnswer>) -> Result<Answer> {
+        let value = res.value;
+
+        let escalate = if value.escalate {
+            Some(
+                value
+                    .reason
+                    .filter(|r| !r.trim().is_empty())
+                    .unwrap_or_else(|| "escalated by model".into()),
+            )
+        } else if value.confidence < self.params.min_confidence {
+            Some(format!("low model confidence {:.2}", value.confidence))
+        } else {
+            None
+        };
+
+        let max_len = self.shop.scli.answer_max_len();
+        let text = match escalate {
+            Some(_) => genai::truncate(&genai::clean_answer(&value.answer), max_len),
+            None => genai::prepare_answer(&value.answer, max_len)?,
+        };
+
+        Ok(Answer {
+            text,
+            model: Some(res.model),
+            sentiment: Some(value.sentiment),
+            confidence: Some(value.confidence.clamp(0.0, 1.0)),
+            escalate,
+        })
+    }
+
+    /// Формирует диалог по шаблону `template` (путь к файлу) или шаблону из `Params`:
+    /// системный промпт и сообщение пользователя (см. `Conversation::from_template`),
+    /// а также примеры одобренных ранее ответов.
+    async fn conversation(
+        &self,
+        feedback: &NewFeedback,
+        ctx: ProductContext,
+        template: Option<&str>,
+    ) -> Result<Conversation> {
+        let mut tera_ctx = Context::from_serialize(&ctx)?;
+
+        let default_template = match feedback {
+            NewFeedback::Question(q) => {
+                tera_ctx.insert("question", &q.text);
+                &self.question_template
+            }
+            NewFeedback::Review(r) => {
+                tera_ctx.insert("review", r);
+                &self.review_template
+            }
+        };
+
+        let template = match template {
+            Some(path) => std::fs::read_to_string(path)?,
+            None => default_template.clone(),
+        };
+
+        let mut conv = Conversation::from_template(&template, |section| {
+            Ok(Tera::one_off(section, &tera_ctx, false)?)
+        })?;
+
+        if self.params.few_shot > 0 {
+            let rows = db::select_approved_feedback(
+                &self.shop.key(""),
+                feedback.kind(),
+                self.params.few_shot,
+            )
+            .await?;
+
+            // Примеры следуют от старых к новым, ближайший к запросу — самый свежий.
+            for row in rows.into_iter().rev() {
+                let (Ok(example), Some(answer)) = (
+                    serde_json::from_str::<NewFeedback>(&row.payload),
+                    row.answer,
+                ) else {
+                    continue;
+                };
+
+                conv = conv.example(example_input(&example), answer);
+            }
+        }
+
+        Ok(conv)
+    }
+
+    /// Публикует ответ на маркетплейсе. Ответ, отредактированный человеком или заданный
+    /// политикой, не сокращается: пустой или слишком длинный ответ не публикуется.
+    async fn publish(&self, feedback: &NewFeedback, answer: &str) -> Result<()> {
+        let answer = answer.trim();
+        let max_len = self.shop.scli.answer_max_len();
+
+        if answer.is_empty() {
+            return Err(Error::InvalidAnswer("answer is empty".into()));
+        }
+        if answer.chars().count() > max_len {
+            return Err(Error::InvalidAnswer(format!(
+                "answer is longer than {max_len} characters"
+            )));
+        }
+
+        match feedback {
+            NewFeedback::Question(q) => {
+                self.shop
+                    .scli
+                    .answer_question(&q.id, answer, Some(&q.product_id))
+                    .await
+            }
+            NewFeedback::Review(r) => self.shop.scli.answer_review(&r.id, answer).await,
+        }
+    }
+}
+
+/// Задача маршрутизации моделей для ответа на обратную связь.
+#[inline]
+fn task(feedback: &NewFeedback) -> Task {
+    match feedback {
+        NewFeedback::Question(_) => Task::Question,
+        NewFeedback::Review(_) => Task::Review,
+    }
+}
+
+/// Сообщение пользователя в примере ответа: текст вопроса или отзыва с оценкой.
+pub(crate) fn example_input(feedback: &NewFeedback) -> String {
+    match feedback {
+        NewFeedback::Question(q) => q.text.clone(),
+        NewFeedback::Review(r) if r.text.trim().is_empty() => {
+            format!("Оценка {} из 5 (без текста)", r.score)
+        }
+        NewFeedback::Review(r) => format!("Оценка {} из 5: {}", r.score, r.text),
+    }
+}
+
+/// Текстовое представление данных товара, аналогичное шаблону `product_summary.j2`.
+/// Используется вместо AI-сводки, если она ещё не сформирована.
+fn format_product_info(p: &ProductFormatInfo) -> String {
+    let mut s = String::new();
+
+    let _ = writeln!(&mut s, "* Название: {}", p.name);
+    let _ = writeln!(&mut s, "* Цена: {}", p.price);
+    let _ = writeln!(&mut s, "* Вес: {}", p.weight);
+    let _ = writeln!(&mut s, "* Размер упаковки: {}", p.r#box);
+    let _ = writeln!(&mut s, "* Описание: {}", p.desc);
+    for (key, value) in &p.attrs {
+        let _ = writeln!(&mut s, "* {key}: {value};");
+    }
+
+    s
+}
+
+#[test]
+fn auto_approve_rule_test() {
+    use crate::sellerapi::abcmodels::{NewQuestion, NewReview};
+
+    let review = |score: f32, text: &str| {
+        NewFeedback::Review(NewReview {
+            id: "1".into(),
+            product_id: "1".into(),
+            author_name: "User".into(),
+            text: text.into(),
+            score,
+            photos_amount: 0,
+            videos_amount: 0,
+            published_at: 0,
+        })
+    };
+
+    let rule = AutoApproveRule::five_star_without_text();
+
+    assert!(rule.matches(&review(5.0, "")));
+    assert!(!rule.matches(&review(5.0, "Отличный товар")));
+    assert!(!rule.matches(&review(4.0, "")));
+    assert!(!rule.matches(&NewFeedback::Question(NewQuestion {
+        id: "1".into(),
+        product_id: "1".into(),
+        author_name: "User".into(),
+        text: String::new(),
+        published_at: 0,
+    })));
+}
+
+#[tokio::test]
+async fn before_publish_approved_answer_test() {
+    use crate::{
+        genai::{AiProvider, MockBackend},
+        sellerapi::{
+            abcmodels::NewQuestion,
+            fake::{self, FakeMarketplace},
+        },
+    };
+
+    let server = FakeMarketplace::start().await;
+    server.with(|d| {
+        d.wb_questions.push(fake::wb_question(
+            "bp-q1",
+            300101,
+            "Какой размер?",
+            "2025-03-01T10:00:00Z",
+        ))
+    });
+
+    let policy = std::env::temp_dir().join(format!("blueberry-policy-{}.lua", std::process::id()));
+    std::fs::write(
+        &policy,
+        r#"function before_publish(answer)
+	if string.find(answer.text, "http", 1, true) then
+		return { action = "escalate", reason = "link" }
+	end
+end"#,
+    )
+    .unwrap();
+
+    let shop = Shop {
+        id: "wb-before-publish".into(),
+        scli: server.wb_seller(),
+        model: "test".into(),
+        question_template: "templates/question.j2".into(),
+        review_template: "templates/review.j2".into(),
+        policy: None,
+        auto_approve: Vec::new(),
+    };
+    db::insert_or_replace_product_ai_summary(&shop.key("300101"), "Платье", None)
+        .await
+        .unwrap();
+
+    let router = Arc::new(ModelRouter::new(
+        AiProvider::Mock(MockBackend::new(Some("Размер 44.".into()))),
+        Default::default(),
+        Duration::from_secs(60),
+    ));
+    let params = Params {
+        model: "test".into(),
+        policy_script: Some(policy.to_string_lossy().into_owned()),
+        ..Default::default()
+    };
+    let controller = FeedbackController::new(shop.clone(), router, params).unwrap();
+
+    let question = NewFeedback::Question(NewQuestion {
+        id: "bp-q1".into(),
+        product_id: "300101".into(),
+        author_name: "User".into(),
+        text: "Какой размер?".into(),
+        published_at: 1,
+    });
+    let key = shop.key("bp-q1");
+    let status = || async { db::select_feedback(&key).await.unwrap().unwrap().status };
+
+    let res = controller.process(question).await.unwrap();
+    assert_eq!(res, FeedbackStatus::Generated);
+
+    // Человек добавил в ответ ссылку: хук передаёт ответ обратно на проверку.
+    assert!(
+        db::approve_feedback(&key, Some("Размер 44, см. http://example.com"))
+            .await
+            .unwrap()
+    );
+    controller
+        .resume(&[FeedbackStatus::Approved])
+        .await
+        .unwrap();
+    assert_eq!(status().await, FeedbackStatus::Escalated);
+    assert!(server.requests("/api/v1/questions").is_empty());
+
+    assert!(
+        db::approve_feedback(&key, Some("Размер 44."))
+            .await
+            .unwrap()
+    );
+    controller
+        .resume(&[FeedbackStatus::Approved])
+        .await
+        .unwrap();
+    assert_eq!(status().await, FeedbackStatus::Published);
+
+    let requests = server.requests("/api/v1/questions");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].body["answer"]["text"], "Размер 44.");
+
+    std::fs::remove_file(&policy).unwrap();
+}
+
+#[tokio::test]
+async fn feedback_state_machine_test() {
+    use crate::{
+        genai::{AiProvider, MockBackend},
+        sellerapi::{
+            abcmodels::NewQuestion,
+            fake::{self, FakeMarketplace, FakeResponse},
+        },
+    };
+
+    let server = FakeMarketplace::start().await;
+    server.with(|d| {
+        d.ozon_questions.push(fake::ozon_question(
+            "sm-q1",
+            1007,
+            "Какой состав?",
+            "2025-03-01T10:00:00Z",
+        ))
+    });
+
+    let shop = Shop {
+        id: "oz-state-machine".into(),
+        scli: server.ozon_seller(),
+        model: "test".into(),
+        question_template: "templates/question.j2".into(),
+        review_template: "templates/review.j2".into(),
+        policy: None,
+        auto_approve: Vec::new(),
+    };
+    db::insert_or_replace_product_ai_summary(&shop.key("1007"), "Футболка из хлопка", None)
+        .await
+        .unwrap();
+
+    let router = Arc::new(ModelRouter::new(
+        AiProvider::Mock(MockBackend::new(Some("Хлопок 100%.".into()))),
+        Default::default(),
+        Duration::from_secs(60),
+    ));
+    let params = Params {
+        model: "test".into(),
+        ..Default::default()
+    };
+    let controller = FeedbackController::new(shop.clone(), router, params).unwrap();
+
+    let question = NewFeedback::Question(NewQuestion {
+        id: "sm-q1".into(),
+        product_id: "1007".into(),
+        author_name: "User".into(),
+        text: "Какой состав?".into(),
+        published_at: 1,
+    });
+    let key = shop.key("sm-q1");
+    let row = || async { db::select_feedback(&key).await.unwrap().unwrap() };
+    let attempts = || async {
+        db::select_all_publish_attempts()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|a| a.feedback_id == key)
+            .map(|a| (a.answer, a.error.is_some()))
+            .collect::<Vec<_>>()
+    };
+    const PATH: &str = "/v1/question/answer/create";
+
+    // received → generated: черновик ждёт одобрения.
+    let status = controller.process(question.clone()).await.unwrap();
+    assert_eq!(status, FeedbackStatus::Generated);
+    assert_eq!(row().await.answer.as_deref(), Some("Хлопок 100%."));
+    assert!(server.requests(PATH).is_empty());
+
+    // generated → approved, публикация не удалась: запись остаётся одобренной.
+    assert!(db::approve_feedback(&key, None).await.unwrap());
+    server.push_response(PATH, FakeResponse::ozon_error(400, 3, "invalid text"));
+    controller
+        .resume(&[FeedbackStatus::Approved])
+        .await
+        .unwrap();
+
+    let failed = row().await;
+    assert_eq!(failed.status, FeedbackStatus::Approved);
+    assert_eq!(failed.attempts, 1);
+    assert!(failed.error.is_some());
+    assert_eq!(attempts().await, [("Хлопок 100%.".to_string(), true)]);
+
+    // approved → published при следующей попытке.
+    controller
+        .resume(&[FeedbackStatus::Approved])
+        .await
+        .unwrap();
+    assert_eq!(row().await.status, FeedbackStatus::Published);
+    assert_eq!(
+        attempts().await,
+        [
+            ("Хлопок 100%.".to_string(), true),
+            ("Хлопок 100%.".to_string(), false)
+        ]
+    );
+
+    let requests = server.requests(PATH);
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].body["text"], "Хлопок 100%.");
+
+    // Повторно полученная обратная связь не публикуется ещё раз.
+    let status = controller.process(question).await.unwrap();
+    assert_eq!(status, FeedbackStatus::Published);
+    assert_eq!(server.requests(PATH).len(), 2);
+}
+
+#[tokio::test]
+async fn process_backlog_page_error_test() {
+    use crate::{
+        genai::{AiProvider, MockBackend},
+        sellerapi::fake::{self, FakeMarketplace, FakeResponse},
+    };
+
+    let server = FakeMarketplace::start().await;
+    server.with(|d| {
+        d.ozon_questions.push(fake::ozon_question(
+            "bl-q1",
+            1008,
+            "Какой состав?",
+            "2025-03-01T10:00:00Z",
+        ));
+        d.ozon_reviews.extend([
+            fake::ozon_review("bl-r1", 1008, "Отлично", 5, "2025-03-01T12:00:00Z"),
+            fake::ozon_review("bl-r2", 1008, "Хорошо", 4, "2025-03-02T12:00:00Z"),
+        ]);
+    });
+    // Выгрузка вопросов завершается ошибкой, отзывы выгружаются дальше.
+    server.push_response(
+        "/v1/question/list",
+        FakeResponse::ozon_error(400, 3, "invalid filter"),
+    );
+
+    let shop = Shop {
+        id: "oz-backlog".into(),
+        scli: server.ozon_seller(),
+        model: "test".into(),
+        question_template: "templates/question.j2".into(),
+        review_template: "templates/review.j2".into(),
+        policy: None,
+        auto_approve: Vec::new(),
+    };
+    db::insert_or_replace_product_ai_summary(&shop.key("1008"), "Футболка", None)
+        .await
+        .unwrap();
+
+    let router = Arc::new(ModelRouter::new(
+        AiProvider::Mock(MockBackend::new(Some("Спасибо!".into()))),
+        Default::default(),
+        Duration::from_secs(60),
+    ));
+    let params = Params {
+        model: "test".into(),
+        ..Default::default()
+    };
+    let controller = FeedbackController::new(shop.clone(), router, params).unwrap();
+
+    let filter = UnansweredFilter::default();
+    assert_eq!(controller.process_backlog(&filter, None).await.unwrap(), 2);
+    for id in ["bl-r1", "bl-r2"] {
+        let row = db::select_feedback(&shop.key(id)).await.unwrap().unwrap();
+        assert_eq!(row.status, FeedbackStatus::Generated);
+    }
+
+    // Пропущенные из-за ошибки вопросы принимаются следующей выгрузкой.
+    assert_eq!(controller.process_backlog(&filter, None).await.unwrap(), 1);
+}
@@ -1,2 +1,5 @@
-mod dotlua;
-mod feedback;
+pub mod dotlua;
+pub mod feedback;
+pub mod tools;
+
+pub use feedback::{FeedbackController, ProductContext};
@@ -0,0 +1,133 @@
+use super::feedback::example_input;
+use crate::{
+    db,
+    error::{Error, Result},
+    genai::{Tool, ToolHandler},
+    sellerapi::abcmodels::NewFeedback,
+    shop::Shop,
+};
+use serde_json::{Value, json};
+
+/// Количество ответов, возвращаемых `search_previous_answers`.
+const SEARCH_LIMIT: u32 = 5;
+
+/// Встроенные инструменты для ответа на обратную связь: данные товаров магазина
+/// из API маркетплейса и ранее одобренные ответы магазина.
+pub struct ProductTools<'a> {
+    shop: &'a Shop,
+    /// Товар обратной связи: используется, если модель не указала `product_id`.
+    product_id: &'a str,
+}
+
+impl<'a> ProductTools<'a> {
+    pub fn new(shop: &'a Shop, product_id: &'a str) -> Self {
+        Self { shop, product_id }
+    }
+
+    fn product_id(&self, args: &Value) -> String {
+        str_arg(args, "product_id").unwrap_or_else(|| self.product_id.to_string())
+    }
+
+    /// Одобренные ответы магазина на обратную связь, содержащую `query`.
+    async fn search_previous_answers(&self, args: &Value) -> Result<String> {
+        let query = str_arg(args, "query")
+            .filter(|q| !q.trim().is_empty())
+            .ok_or_else(|| Error::MissingRequiredField("query".into()))?;
+
+        let rows =
+            db::search_approved_feedback(&self.shop.key(""), query.trim(), SEARCH_LIMIT).await?;
+
+        let answers = rows
+            .into_iter()
+            .filter_map(|row| {
+                let feedback = serde_json::from_str::<NewFeedback>(&row.payload).ok()?;
+                Some(json!({
+                    "kind": row.kind,
+                    "product_id": feedback.product_id(),
+                    "feedback": example_input(&feedback),
+                    "answer": row.answer?,
+                }))
+            })
+            .collect::<Vec<_>>();
+
+        Ok(serde_json::to_string(&answers)?)
+    }
+}
+
+impl ToolHandler for ProductTools<'_> {
+    fn tools(&self) -> Vec<Tool> {
+        let product = json!({
+            "type": "object",
+            "properties": {
+                "product_id": {
+                    "type": "string",
+                    "description": "Идентификатор товара. По умолчанию товар, о котором спрашивает покупатель."
+                }
+            }
+        });
+
+        vec![
+            Tool::function(
+                "get_product_info",
+                "Название, описание, характеристики, вес и размеры упаковки товара.",
+                product.clone(),
+            ),
+            Tool::function(
+                "get_price",
+                "Текущая цена товара для покупателя и цена без скидок.",
+                product.clone(),
+            ),
+            Tool::function(
+                "get_stock",
+                "Остатки товара, доступные для продажи, по складам.",
+                product,
+            ),
+            Tool::function(
+                "search_previous_answers",
+                "Поиск ранее одобренных ответов магазина на похожие вопросы и отзывы.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "Слово или фраза для поиска в тексте обратной связи и ответа."
+                        }
+                    },
+                    "required": ["query"]
+                }),
+            ),
+        ]
+    }
+
+    async fn call(&self, name: &str, args: Value) -> Result<String> {
+        let scli = &self.shop.scli;
+
+        match name {
+            "get_product_info" => {
+                let product = scli
+                    .get_product_format_info(&self.product_id(&args))
+                    .await?;
+                Ok(serde_json::to_string(&product)?)
+            }
+            "get_price" => {
+                let price = scli.get_product_price(&self.product_id(&args)).await?;
+                Ok(serde_json::to_string(&price)?)
+            }
+            "get_stock" => {
+                let stock = scli.get_product_stock(&self.product_id(&args)).await?;
+                Ok(serde_json::to_string(&stock)?)
+            }
+            "search_previous_answers" => self.search_previous_answers(&args).await,
+            _ => Err(Error::AiProvider(format!("unknown tool {name}"))),
+        }
+    }
+}
+
+/// Строковый аргумент. Числа (например, SKU) преобразуются в строку.
+fn str_arg(args: &Value, key: &str) -> Option<String> {
+    match args.get(key)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
@@ -1,65 +1,1454 @@
-use crate::error::Result;
+use crate::{
+    config::DbConfig,
+    error::{Error, Result},
+    sellerapi::abcmodels::ProductFormatInfo,
+};
 use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
 use std::{
     default,
-    sync::{LazyLock, Mutex},
+    sync::{LazyLock, Mutex, OnceLock},
+    time::Duration,
 };
+use tokio::sync::Semaphore;
+
+static CONFIG: OnceLock<DbConfig> = OnceLock::new();
+
+/// Открытые соединения, ожидающие запроса.
+static IDLE: Mutex<Vec<Connection>> = Mutex::new(Vec::new());
+
+/// Ограничивает число одновременно используемых соединений размером пула.
+static PERMITS: LazyLock<Semaphore> = LazyLock::new(|| Semaphore::new(config().pool_size));
+
+/// Миграции применяются первым открытым соединением.
+static MIGRATED: Mutex<bool> = Mutex::new(false);
+
+/// Задаёт параметры базы данных. Должна вызываться до первого запроса.
+pub fn configure(cfg: &DbConfig) -> Result<()> {
+    CONFIG
+        .set(cfg.clone())
+        .map_err(|_| Error::Config("database is already configured".into()))
+}
+
+fn config() -> &'static DbConfig {
+    CONFIG.get_or_init(default_config)
+}
+
+#[cfg(not(test))]
+fn default_config() -> DbConfig {
+    DbConfig::default()
+}
+
+/// Тесты работают с отдельной базой данных процесса во временном каталоге,
+/// а не с базой данных приложения в рабочем каталоге.
+#[cfg(test)]
+fn default_config() -> DbConfig {
+    let path = std::env::temp_dir().join(format!("blueberry-test-{}.db", std::process::id()));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+
+    DbConfig {
+        path: path.to_string_lossy().into_owned(),
+        ..Default::default()
+    }
+}
+
+/// Выполняет `f` с соединением из пула в потоке для блокирующих операций,
+/// не занимая рабочие потоки tokio.
+async fn with_conn<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+{
+    let permit = PERMITS.acquire().await.expect("semaphore is never closed");
+
+    let res = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+
+        let idle = IDLE.lock().unwrap().pop();
+        let mut conn = match idle {
+            Some(conn) => conn,
+            None => open_conn()?,
+        };
+
+        let res = f(&mut conn);
+        IDLE.lock().unwrap().push(conn);
+
+        res
+    })
+    .await;
+
+    match res {
+        Ok(res) => res,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
 
-const DB_PATH: &str = ".db";
+fn open_conn() -> Result<Connection> {
+    let cfg = config();
 
-pub static CONN: LazyLock<Mutex<Connection>> = LazyLock::new(|| {
-    let conn = create_new_conn().unwrap();
+    let mut conn = Connection::open(&cfg.path)?;
+    conn.busy_timeout(Duration::from_millis(cfg.busy_timeout_ms))?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
 
-    Mutex::new(conn)
-});
+    let mut migrated = MIGRATED.lock().unwrap();
+    if !*migrated {
+        migrate(&mut conn)?;
+        *migrated = true;
+    }
 
-fn create_new_conn() -> Result<Connection> {
-    let conn = Connection::open(DB_PATH)?;
+    Ok(conn)
+}
+
+/// Миграция схемы базы данных. Миграция с индексом `i` переводит схему в версию `i + 1`.
+type Migration = fn(&Connection) -> Result<()>;
+
+const MIGRATIONS: [Migration; 3] = [migrate_v1, migrate_v2, migrate_v3];
+
+/// Применяет к базе данных недостающие миграции, каждую в отдельной транзакции.
+/// Номера применённых версий хранятся в таблице `schema_version`.
+fn migrate(conn: &mut Connection) -> Result<()> {
+    const CREATE_SQL: &str = "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER PRIMARY KEY, applied_at INTEGER NOT NULL)";
+    const VERSION_SQL: &str = "SELECT COALESCE(MAX(version), 0) FROM schema_version";
+    const INSERT_SQL: &str =
+        "INSERT INTO schema_version (version, applied_at) VALUES (?1, strftime('%s','now'))";
+
+    conn.execute(CREATE_SQL, [])?;
+
+    let version: usize = conn.query_row(VERSION_SQL, [], |row| row.get(0))?;
+    if version > MIGRATIONS.len() {
+        return Err(Error::Config(format!(
+            "database schema version {version} is newer than supported version {}",
+            MIGRATIONS.len()
+        )));
+    }
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        migration(&tx)?;
+        tx.execute(INSERT_SQL, [i + 1])?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
 
+/// Исходная схема. Таблицы создаются, только если их нет, чтобы принять базу данных,
+/// созданную до появления версий схемы.
+fn migrate_v1(conn: &Connection) -> Result<()> {
     conn.execute_batch(
-        r#"BEGIN;
-CREATE TABLE IF NOT EXISTS product_ai_summary (
+        r#"CREATE TABLE IF NOT EXISTS product_ai_summary (
 	id TEXT PRIMARY KEY,
 	ai_summary TEXT NOT NULL,
 	created_at INTEGER NOT NULL
 );
-COMMIT;"#,
+CREATE TABLE IF NOT EXISTS feedback (
+	id TEXT PRIMARY KEY,
+	kind TEXT NOT NULL,
+	payload TEXT NOT NULL,
+	status TEXT NOT NULL,
+	answer TEXT,
+	error TEXT,
+	attempts INTEGER NOT NULL DEFAULT 0,
+	created_at INTEGER NOT NULL,
+	updated_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS feedback_status_idx ON feedback (status);
+CREATE TABLE IF NOT EXISTS observer_cursor (
+	id TEXT PRIMARY KEY,
+	last_seen INTEGER NOT NULL,
+	updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS observer_seen (
+	cursor_id TEXT NOT NULL,
+	feedback_id TEXT NOT NULL,
+	published_at INTEGER NOT NULL,
+	PRIMARY KEY (cursor_id, feedback_id)
+);
+CREATE TABLE IF NOT EXISTS ai_call (
+	id INTEGER PRIMARY KEY AUTOINCREMENT,
+	shop TEXT NOT NULL,
+	task TEXT NOT NULL,
+	model TEXT NOT NULL,
+	prompt_tokens INTEGER NOT NULL,
+	completion_tokens INTEGER NOT NULL,
+	latency_ms INTEGER NOT NULL,
+	cost REAL NOT NULL,
+	error TEXT,
+	created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS ai_call_created_at_idx ON ai_call (created_at);"#,
     )?;
 
-    Ok(conn)
+    add_column_if_absent(conn, "feedback", "model", "TEXT")?;
+    add_column_if_absent(conn, "feedback", "sentiment", "TEXT")?;
+    add_column_if_absent(conn, "feedback", "confidence", "REAL")?;
+
+    Ok(())
 }
 
-#[derive(Debug)]
+/// Магазины, товары, история ответов и попыток публикации.
+fn migrate_v2(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"CREATE TABLE shop (
+	id TEXT PRIMARY KEY,
+	place TEXT NOT NULL,
+	created_at INTEGER NOT NULL,
+	updated_at INTEGER NOT NULL
+);
+CREATE TABLE product (
+	shop_id TEXT NOT NULL,
+	id TEXT NOT NULL,
+	name TEXT NOT NULL,
+	info TEXT NOT NULL,
+	updated_at INTEGER NOT NULL,
+	PRIMARY KEY (shop_id, id)
+);
+CREATE TABLE answer (
+	id INTEGER PRIMARY KEY AUTOINCREMENT,
+	feedback_id TEXT NOT NULL,
+	text TEXT NOT NULL,
+	source TEXT NOT NULL,
+	model TEXT,
+	created_at INTEGER NOT NULL
+);
+CREATE INDEX answer_feedback_id_idx ON answer (feedback_id);
+CREATE TABLE publish_attempt (
+	id INTEGER PRIMARY KEY AUTOINCREMENT,
+	feedback_id TEXT NOT NULL,
+	answer TEXT NOT NULL,
+	error TEXT,
+	created_at INTEGER NOT NULL
+);
+CREATE INDEX publish_attempt_feedback_id_idx ON publish_attempt (feedback_id);"#,
+    )?;
+
+    Ok(())
+}
+
+/// Исходные данные AI-сводок для определения устаревших сводок.
+fn migrate_v3(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"ALTER TABLE product_ai_summary ADD COLUMN content_hash TEXT;
+ALTER TABLE product_ai_summary ADD COLUMN template TEXT;
+ALTER TABLE product_ai_summary ADD COLUMN model TEXT;"#,
+    )?;
+
+    Ok(())
+}
+
+/// Добавляет столбец в таблицу, созданную предыдущей версией схемы.
+fn add_column_if_absent(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let exists = conn
+        .prepare(&format!(
+            "SELECT 1 FROM pragma_table_info('{table}') WHERE name = ?1"
+        ))?
+        .exists([column])?;
+
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl};"))?;
+    }
+
+    Ok(())
+}
+
+/// Исходные данные AI-сводки. Сводка устарела, если любое из значений изменилось.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SummarySource {
+    /// Хэш данных товара (см. `ProductFormatInfo::content_hash`).
+    pub content_hash: String,
+    /// Шаблон промпта.
+    pub template: String,
+    /// Запрошенная модель AI провайдера.
+    pub model: String,
+}
+
+#[derive(Debug, Serialize)]
 pub struct ProductAiSummaryRow {
     pub id: String,
     pub ai_summary: String,
     pub created_at: u64,
+    /// `None` для сводок, сохранённых без исходных данных.
+    #[serde(flatten)]
+    pub source: Option<SummarySource>,
+}
+
+impl ProductAiSummaryRow {
+    /// Сводка сформирована по тем же данным товара, шаблону и модели.
+    #[inline]
+    pub fn is_fresh(&self, source: &SummarySource) -> bool {
+        self.source.as_ref() == Some(source)
+    }
+}
+
+const SUMMARY_COLUMNS: &str = "id, ai_summary, created_at, content_hash, template, model";
+
+fn map_summary_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ProductAiSummaryRow> {
+    let content_hash: Option<String> = row.get(3)?;
+    let template: Option<String> = row.get(4)?;
+    let model: Option<String> = row.get(5)?;
+
+    Ok(ProductAiSummaryRow {
+        id: row.get(0)?,
+        ai_summary: row.get(1)?,
+        created_at: row.get(2)?,
+        source: match (content_hash, template, model) {
+            (Some(content_hash), Some(template), Some(model)) => Some(SummarySource {
+                content_hash,
+                template,
+                model,
+            }),
+            _ => None,
+        },
+    })
+}
+
+/// Сохраняет AI-сводку о товаре и её исходные данные `source`, если они известны.
+pub async fn insert_or_replace_product_ai_summary(
+    id: &str,
+    ai_summary: &str,
+    source: Option<&SummarySource>,
+) -> Result<()> {
+    let id = id.to_string();
+    let ai_summary = ai_summary.to_string();
+    let source = source.cloned();
+
+    with_conn(move |conn| {
+        const SQL: &str = "INSERT OR REPLACE INTO product_ai_summary (id, ai_summary, created_at, content_hash, template, model) VALUES (?1, ?2, strftime('%s','now'), ?3, ?4, ?5)";
+
+        conn.execute(
+            SQL,
+            rusqlite::params![
+                id,
+                ai_summary,
+                source.as_ref().map(|s| &s.content_hash),
+                source.as_ref().map(|s| &s.template),
+                source.as_ref().map(|s| &s.model)
+            ],
+        )?;
+
+        Ok(())
+    })
+    .await
+}
+
+pub async fn select_product_ai_summary(id: &str) -> Result<Option<ProductAiSummaryRow>> {
+    let id = id.to_string();
+
+    with_conn(move |conn| {
+        let sql = format!("SELECT {SUMMARY_COLUMNS} FROM product_ai_summary WHERE id = ?1");
+
+        let row = conn.query_one(&sql, [id], map_summary_row).optional()?;
+
+        Ok(row)
+    })
+    .await
+}
+
+pub async fn select_all_product_ai_summaries() -> Result<Vec<ProductAiSummaryRow>> {
+    with_conn(move |conn| {
+        let sql = format!("SELECT {SUMMARY_COLUMNS} FROM product_ai_summary ORDER BY id");
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map([], map_summary_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+/// Этап обработки обратной связи (вопроса или отзыва).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackStatus {
+    /// Получена от наблюдателя, ответ ещё не сгенерирован.
+    Received,
+    /// Черновик ответа сгенерирован и ожидает проверки.
+    Generated,
+    /// Ответ одобрен и ожидает публикации.
+    Approved,
+    /// Ответ опубликован.
+    Published,
+    /// Ответ отклонён при проверке.
+    Rejected,
+    /// Политика обработки передала обратную связь человеку.
+    Escalated,
+    /// Обработка пропущена.
+    Skipped,
+}
+
+impl FeedbackStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Received => "received",
+            Self::Generated => "generated",
+            Self::Approved => "approved",
+            Self::Published => "published",
+            Self::Rejected => "rejected",
+            Self::Escalated => "escalated",
+            Self::Skipped => "skipped",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "received" => Some(Self::Received),
+            "generated" => Some(Self::Generated),
+            "approved" => Some(Self::Approved),
+            "published" => Some(Self::Published),
+            "rejected" => Some(Self::Rejected),
+            "escalated" => Some(Self::Escalated),
+            "skipped" => Some(Self::Skipped),
+            _ => None,
+        }
+    }
+
+    /// Обработка завершена и повторно выполняться не должна.
+    #[inline]
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Published | Self::Rejected | Self::Skipped)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedbackRow {
+    pub id: String,
+    pub kind: String,
+    pub payload: String,
+    pub status: FeedbackStatus,
+    pub answer: Option<String>,
+    /// Текст последней ошибки обработки или причина эскалации.
+    pub error: Option<String>,
+    /// Количество неудачных попыток обработки.
+    pub attempts: u32,
+    pub created_at: u64,
+    pub updated_at: u64,
+    /// Модель, сформировавшая черновик ответа. `None`, если ответ задан политикой.
+    pub model: Option<String>,
+}
+
+const FEEDBACK_COLUMNS: &str =
+    "id, kind, payload, status, answer, error, attempts, created_at, updated_at, model";
+
+fn map_feedback_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<FeedbackRow> {
+    let status: String = row.get(3)?;
+
+    Ok(FeedbackRow {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        payload: row.get(2)?,
+        status: FeedbackStatus::parse(&status).unwrap_or(FeedbackStatus::Received),
+        answer: row.get(4)?,
+        error: row.get(5)?,
+        attempts: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+        model: row.get(9)?,
+    })
+}
+
+/// Сохраняет новую обратную связь со статусом `received`.
+/// Возвращает `false`, если запись с таким id уже существует.
+pub async fn insert_feedback_if_absent(id: &str, kind: &str, payload: &str) -> Result<bool> {
+    let id = id.to_string();
+    let kind = kind.to_string();
+    let payload = payload.to_string();
+
+    with_conn(move |conn| {
+        const SQL: &str = "INSERT OR IGNORE INTO feedback (id, kind, payload, status, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, strftime('%s','now'), strftime('%s','now'))";
+
+        let inserted = conn.execute(
+            SQL,
+            rusqlite::params![id, kind, payload, FeedbackStatus::Received.as_str()],
+        )?;
+
+        Ok(inserted > 0)
+    })
+    .await
+}
+
+pub async fn select_feedback(id: &str) -> Result<Option<FeedbackRow>> {
+    let id = id.to_string();
+
+    with_conn(move |conn| {
+        let sql = format!("SELECT {FEEDBACK_COLUMNS} FROM feedback WHERE id = ?1");
+
+        let row = conn.query_one(&sql, [id], map_feedback_row).optional()?;
+
+        Ok(row)
+    })
+    .await
+}
+
+pub async fn select_all_feedback() -> Result<Vec<FeedbackRow>> {
+    with_conn(move |conn| {
+        let sql = format!("SELECT {FEEDBACK_COLUMNS} FROM feedback ORDER BY created_at");
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map([], map_feedback_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+/// Возвращает записи в статусе `status`, от новых к старым.
+pub async fn select_feedback_by_status(
+    status: FeedbackStatus,
+    limit: u32,
+) -> Result<Vec<FeedbackRow>> {
+    with_conn(move |conn| {
+        let sql = format!(
+            "SELECT {FEEDBACK_COLUMNS} FROM feedback WHERE status = ?1 ORDER BY created_at DESC LIMIT ?2"
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(rusqlite::params![status.as_str(), limit], map_feedback_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+/// Возвращает записи типа `kind` с префиксом id `prefix` и ответом, одобренным
+/// человеком или правилом (статусы `approved` и `published`), от новых к старым.
+pub async fn select_approved_feedback(
+    prefix: &str,
+    kind: &str,
+    limit: u32,
+) -> Result<Vec<FeedbackRow>> {
+    let prefix = prefix.to_string();
+    let kind = kind.to_string();
+
+    with_conn(move |conn| {
+        let sql = format!(
+            "SELECT {FEEDBACK_COLUMNS} FROM feedback WHERE substr(id, 1, length(?1)) = ?1 AND kind = ?2 AND status IN ('approved', 'published') AND answer IS NOT NULL ORDER BY updated_at DESC LIMIT ?3"
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(rusqlite::params![prefix, kind, limit], map_feedback_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+/// Ищет записи с префиксом id `prefix` и одобренным ответом, в тексте обратной связи
+/// или ответа которых встречается `query`, от новых к старым.
+pub async fn search_approved_feedback(
+    prefix: &str,
+    query: &str,
+    limit: u32,
+) -> Result<Vec<FeedbackRow>> {
+    let prefix = prefix.to_string();
+    let query = query.to_string();
+
+    with_conn(move |conn| {
+        let sql = format!(
+            "SELECT {FEEDBACK_COLUMNS} FROM feedback WHERE substr(id, 1, length(?1)) = ?1 AND status IN ('approved', 'published') AND answer IS NOT NULL AND (payload LIKE '%' || ?2 || '%' OR answer LIKE '%' || ?2 || '%') ORDER BY updated_at DESC LIMIT ?3"
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(rusqlite::params![prefix, query, limit], map_feedback_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+/// Возвращает незавершённые записи в статусах `statuses` с префиксом id `prefix`
+/// (например, `"wb/"`), у которых число попыток меньше `max_attempts`.
+pub async fn select_unfinished_feedback(
+    prefix: &str,
+    statuses: &[FeedbackStatus],
+    max_attempts: u32,
+) -> Result<Vec<FeedbackRow>> {
+    let prefix = prefix.to_string();
+    let statuses = statuses.to_vec();
+
+    with_conn(move |conn| {
+        let statuses = statuses
+            .iter()
+            .map(|s| format!("'{}'", s.as_str()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let sql = format!(
+            "SELECT {FEEDBACK_COLUMNS} FROM feedback WHERE substr(id, 1, length(?1)) = ?1 AND status IN ({statuses}) AND attempts < ?2 ORDER BY created_at"
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(rusqlite::params![prefix, max_attempts], map_feedback_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+/// Переводит запись в статус `generated` (черновик) и сохраняет сгенерированный ответ
+/// и модель `model`, которая его сформировала.
+/// Ответ сохраняется в историю ответов.
+pub async fn set_feedback_generated(id: &str, answer: &str, model: Option<&str>) -> Result<()> {
+    let id = id.to_string();
+    let answer = answer.to_string();
+    let model = model.map(str::to_string);
+
+    with_conn(move |conn| {
+        let tx = conn.transaction()?;
+
+        const SQL: &str = "UPDATE feedback SET status = ?2, answer = ?3, model = ?4, error = NULL, updated_at = strftime('%s','now') WHERE id = ?1";
+
+        tx.execute(
+            SQL,
+            rusqlite::params![id, FeedbackStatus::Generated.as_str(), answer, model],
+        )?;
+
+        let source = match model {
+            Some(_) => AnswerSource::Model,
+            None => AnswerSource::Policy,
+        };
+        insert_answer(&tx, &id, &answer, source, model.as_deref())?;
+
+        tx.commit()?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Сохраняет оценку обратной связи моделью: тональность и уверенность в ответе.
+pub async fn set_feedback_analysis(id: &str, sentiment: &str, confidence: f32) -> Result<()> {
+    let id = id.to_string();
+    let sentiment = sentiment.to_string();
+
+    with_conn(move |conn| {
+        const SQL: &str = "UPDATE feedback SET sentiment = ?2, confidence = ?3 WHERE id = ?1";
+
+        conn.execute(SQL, rusqlite::params![id, sentiment, confidence])?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Меняет статус записи, если её текущий статус равен `from`.
+/// Возвращает `false`, если запись не найдена или находится в другом статусе.
+pub async fn transition_feedback(
+    id: &str,
+    from: FeedbackStatus,
+    to: FeedbackStatus,
+) -> Result<bool> {
+    let id = id.to_string();
+
+    with_conn(move |conn| {
+        const SQL: &str = "UPDATE feedback SET status = ?3, error = NULL, updated_at = strftime('%s','now') WHERE id = ?1 AND status = ?2";
+
+        let updated = conn.execute(SQL, rusqlite::params![id, from.as_str(), to.as_str()])?;
+
+        Ok(updated > 0)
+    })
+    .await
+}
+
+/// Заменяет текст черновика ответа.
+/// Доступно только в статусах `generated` и `escalated`.
+pub async fn update_feedback_draft(id: &str, answer: &str) -> Result<bool> {
+    let id = id.to_string();
+    let answer = answer.to_string();
+
+    with_conn(move |conn| {
+        let tx = conn.transaction()?;
+
+        const SQL: &str = "UPDATE feedback SET answer = ?2, updated_at = strftime('%s','now') WHERE id = ?1 AND status IN ('generated', 'escalated')";
+
+        let updated = tx.execute(SQL, [&id, &answer])?;
+        if updated > 0 {
+            insert_answer(&tx, &id, &answer, AnswerSource::Human, None)?;
+        }
+
+        tx.commit()?;
+
+        Ok(updated > 0)
+    })
+    .await
+}
+
+/// Одобряет ответ, находящийся на проверке (`generated` или `escalated`).
+/// Если передан `answer`, он заменяет текст черновика.
+/// Возвращает `false`, если запись не на проверке или у неё нет текста ответа.
+pub async fn approve_feedback(id: &str, answer: Option<&str>) -> Result<bool> {
+    let id = id.to_string();
+    let answer = answer.map(str::to_string);
+
+    with_conn(move |conn| {
+        let tx = conn.transaction()?;
+
+        const SQL: &str = "UPDATE feedback SET status = 'approved', answer = COALESCE(?2, answer), error = NULL, attempts = 0, updated_at = strftime('%s','now') WHERE id = ?1 AND status IN ('generated', 'escalated') AND COALESCE(?2, answer) IS NOT NULL";
+
+        let updated = tx.execute(SQL, rusqlite::params![id, answer])?;
+        if let Some(answer) = answer.as_deref().filter(|_| updated > 0) {
+            insert_answer(&tx, &id, answer, AnswerSource::Human, None)?;
+        }
+
+        tx.commit()?;
+
+        Ok(updated > 0)
+    })
+    .await
+}
+
+/// Заменяет текст одобренного ответа ответом, заданным политикой перед публикацией.
+pub async fn rewrite_approved_feedback(id: &str, answer: &str) -> Result<bool> {
+    let id = id.to_string();
+    let answer = answer.to_string();
+
+    with_conn(move |conn| {
+        let tx = conn.transaction()?;
+
+        const SQL: &str = "UPDATE feedback SET answer = ?2, updated_at = strftime('%s','now') WHERE id = ?1 AND status = 'approved'";
+
+        let updated = tx.execute(SQL, [&id, &answer])?;
+        if updated > 0 {
+            insert_answer(&tx, &id, &answer, AnswerSource::Policy, None)?;
+        }
+
+        tx.commit()?;
+
+        Ok(updated > 0)
+    })
+    .await
+}
+
+/// Отклоняет ответ, находящийся на проверке (`generated` или `escalated`).
+pub async fn reject_feedback(id: &str) -> Result<bool> {
+    let id = id.to_string();
+
+    with_conn(move |conn| {
+        const SQL: &str = "UPDATE feedback SET status = 'rejected', updated_at = strftime('%s','now') WHERE id = ?1 AND status IN ('generated', 'escalated')";
+
+        let updated = conn.execute(SQL, [id])?;
+
+        Ok(updated > 0)
+    })
+    .await
+}
+
+/// Сбрасывает счётчик попыток и ошибку записи, обработка которой не завершена
+/// (`received` или `approved`), чтобы контроллер снова взял её в работу.
+/// Возвращает `false`, если запись не найдена или уже не обрабатывается.
+pub async fn retry_feedback(id: &str) -> Result<bool> {
+    let id = id.to_string();
+
+    with_conn(move |conn| {
+        const SQL: &str = "UPDATE feedback SET error = NULL, attempts = 0, updated_at = strftime('%s','now') WHERE id = ?1 AND status IN ('received', 'approved')";
+
+        let updated = conn.execute(SQL, [id])?;
+
+        Ok(updated > 0)
+    })
+    .await
+}
+
+/// Передаёт запись человеку с указанием причины.
+pub async fn set_feedback_escalated(id: &str, reason: &str) -> Result<()> {
+    let id = id.to_string();
+    let reason = reason.to_string();
+
+    with_conn(move |conn| {
+        const SQL: &str = "UPDATE feedback SET status = ?2, error = ?3, updated_at = strftime('%s','now') WHERE id = ?1";
+
+        conn.execute(
+            SQL,
+            rusqlite::params![id, FeedbackStatus::Escalated.as_str(), reason],
+        )?;
+
+        Ok(())
+    })
+    .await
 }
 
-pub fn insert_or_replace_product_ai_summary(id: &str, ai_summary: &str) -> Result<()> {
-    let conn = CONN.lock().unwrap();
+/// Переводит запись в статус `skipped`, сохраняя причину пропуска.
+pub async fn set_feedback_skipped(id: &str, reason: Option<&str>) -> Result<()> {
+    let id = id.to_string();
+    let reason = reason.map(str::to_string);
+
+    with_conn(move |conn| {
+        const SQL: &str = "UPDATE feedback SET status = ?2, error = ?3, updated_at = strftime('%s','now') WHERE id = ?1";
+
+        conn.execute(
+            SQL,
+            rusqlite::params![id, FeedbackStatus::Skipped.as_str(), reason],
+        )?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Фиксирует ошибку обработки и увеличивает счётчик попыток.
+/// Статус записи не меняется, поэтому следующая попытка продолжит с того же этапа.
+pub async fn set_feedback_failed(id: &str, error: &str) -> Result<()> {
+    let id = id.to_string();
+    let error = error.to_string();
+
+    with_conn(move |conn| {
+        const SQL: &str = "UPDATE feedback SET error = ?2, attempts = attempts + 1, updated_at = strftime('%s','now') WHERE id = ?1";
+
+        conn.execute(SQL, [id, error])?;
 
-    const SQL: &str = "INSERT OR REPLACE INTO product_ai_summary (id, ai_summary, created_at) VALUES (?1, ?2, strftime('%s','now'))";
+        Ok(())
+    })
+    .await
+}
+
+/// Сохранённая позиция наблюдателя: `id` — `"{shop_id}/{kind}"`.
+#[derive(Debug, Serialize)]
+pub struct ObserverCursorRow {
+    pub id: String,
+    /// Время публикации последней полученной записи (Unix timestamp).
+    pub last_seen: u64,
+    /// Идентификаторы полученных записей, опубликованных в момент `last_seen`.
+    pub seen_ids: Vec<String>,
+}
+
+pub async fn select_observer_cursor(id: &str) -> Result<Option<ObserverCursorRow>> {
+    let id = id.to_string();
+
+    with_conn(move |conn| {
+        const SQL: &str = "SELECT last_seen FROM observer_cursor WHERE id = ?1";
+        const SEEN_SQL: &str =
+            "SELECT feedback_id FROM observer_seen WHERE cursor_id = ?1 AND published_at >= ?2";
+
+        let Some(last_seen) = conn
+            .query_one(SQL, [&id], |row| row.get::<_, u64>(0))
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn.prepare(SEEN_SQL)?;
+        let seen_ids = stmt
+            .query_map(rusqlite::params![id, last_seen], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(Some(ObserverCursorRow {
+            id,
+            last_seen,
+            seen_ids,
+        }))
+    })
+    .await
+}
 
-    conn.execute(SQL, &[id, ai_summary])?;
+/// Создаёт позицию наблюдателя `id`, если её ещё нет.
+pub async fn insert_observer_cursor_if_absent(id: &str, last_seen: u64) -> Result<()> {
+    let id = id.to_string();
+
+    with_conn(move |conn| {
+        const SQL: &str = "INSERT OR IGNORE INTO observer_cursor (id, last_seen, updated_at) VALUES (?1, ?2, strftime('%s','now'))";
+
+        conn.execute(SQL, rusqlite::params![id, last_seen])?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// В одной транзакции сохраняет обратную связь, полученную наблюдателем, со статусом `received`
+/// и сдвигает позицию наблюдателя `cursor_id`.
+/// Возвращает `false`, если запись `id` уже существует, то есть была получена ранее.
+pub async fn insert_observed_feedback(
+    cursor_id: &str,
+    feedback_id: &str,
+    published_at: u64,
+    id: &str,
+    kind: &str,
+    payload: &str,
+) -> Result<bool> {
+    let cursor_id = cursor_id.to_string();
+    let feedback_id = feedback_id.to_string();
+    let id = id.to_string();
+    let kind = kind.to_string();
+    let payload = payload.to_string();
+
+    with_conn(move |conn| {
+        let tx = conn.transaction()?;
+
+        const FEEDBACK_SQL: &str = "INSERT OR IGNORE INTO feedback (id, kind, payload, status, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, strftime('%s','now'), strftime('%s','now'))";
+        const CURSOR_SQL: &str = "INSERT INTO observer_cursor (id, last_seen, updated_at) VALUES (?1, ?2, strftime('%s','now')) ON CONFLICT (id) DO UPDATE SET last_seen = MAX(last_seen, excluded.last_seen), updated_at = excluded.updated_at";
+        const SEEN_SQL: &str = "INSERT OR IGNORE INTO observer_seen (cursor_id, feedback_id, published_at) VALUES (?1, ?2, ?3)";
+        const PRUNE_SQL: &str = "DELETE FROM observer_seen WHERE cursor_id = ?1 AND published_at < (SELECT last_seen FROM observer_cursor WHERE id = ?1)";
+
+        let inserted = tx.execute(
+            FEEDBACK_SQL,
+            rusqlite::params![id, kind, payload, FeedbackStatus::Received.as_str()],
+        )?;
+        tx.execute(CURSOR_SQL, rusqlite::params![cursor_id, published_at])?;
+        tx.execute(
+            SEEN_SQL,
+            rusqlite::params![cursor_id, feedback_id, published_at],
+        )?;
+        tx.execute(PRUNE_SQL, [cursor_id])?;
+
+        tx.commit()?;
+
+        Ok(inserted > 0)
+    })
+    .await
+}
+
+/// Источник текста ответа в истории ответов.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnswerSource {
+    /// Сгенерирован моделью.
+    Model,
+    /// Задан политикой обработки.
+    Policy,
+    /// Написан или отредактирован человеком.
+    Human,
+}
+
+impl AnswerSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Model => "model",
+            Self::Policy => "policy",
+            Self::Human => "human",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "model" => Some(Self::Model),
+            "policy" => Some(Self::Policy),
+            "human" => Some(Self::Human),
+            _ => None,
+        }
+    }
+}
+
+/// Версия текста ответа на обратную связь `feedback_id`.
+#[derive(Debug, Serialize)]
+pub struct AnswerRow {
+    pub id: i64,
+    pub feedback_id: String,
+    pub text: String,
+    pub source: AnswerSource,
+    pub model: Option<String>,
+    pub created_at: u64,
+}
+
+fn insert_answer(
+    conn: &Connection,
+    feedback_id: &str,
+    text: &str,
+    source: AnswerSource,
+    model: Option<&str>,
+) -> Result<()> {
+    const SQL: &str = "INSERT INTO answer (feedback_id, text, source, model, created_at) VALUES (?1, ?2, ?3, ?4, strftime('%s','now'))";
+
+    conn.execute(
+        SQL,
+        rusqlite::params![feedback_id, text, source.as_str(), model],
+    )?;
 
     Ok(())
 }
 
-pub fn select_product_ai_summary(id: &str) -> Result<Option<ProductAiSummaryRow>> {
-    let conn = CONN.lock().unwrap();
+fn map_answer_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<AnswerRow> {
+    let source: String = row.get(3)?;
+
+    Ok(AnswerRow {
+        id: row.get(0)?,
+        feedback_id: row.get(1)?,
+        text: row.get(2)?,
+        source: AnswerSource::parse(&source).unwrap_or(AnswerSource::Model),
+        model: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+/// История ответов на обратную связь `feedback_id`, от старых к новым.
+pub async fn select_answers(feedback_id: &str) -> Result<Vec<AnswerRow>> {
+    let feedback_id = feedback_id.to_string();
+
+    with_conn(move |conn| {
+        const SQL: &str = "SELECT id, feedback_id, text, source, model, created_at FROM answer WHERE feedback_id = ?1 ORDER BY id";
+
+        let mut stmt = conn.prepare(SQL)?;
+        let rows = stmt
+            .query_map([feedback_id], map_answer_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+pub async fn select_all_answers() -> Result<Vec<AnswerRow>> {
+    with_conn(move |conn| {
+        const SQL: &str =
+            "SELECT id, feedback_id, text, source, model, created_at FROM answer ORDER BY id";
+
+        let mut stmt = conn.prepare(SQL)?;
+        let rows = stmt
+            .query_map([], map_answer_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+/// Попытка публикации ответа на маркетплейсе.
+#[derive(Debug, Serialize)]
+pub struct PublishAttemptRow {
+    pub id: i64,
+    pub feedback_id: String,
+    pub answer: String,
+    /// Текст ошибки, если публикация не удалась.
+    pub error: Option<String>,
+    pub created_at: u64,
+}
+
+pub async fn insert_publish_attempt(
+    feedback_id: &str,
+    answer: &str,
+    error: Option<&str>,
+) -> Result<()> {
+    let feedback_id = feedback_id.to_string();
+    let answer = answer.to_string();
+    let error = error.map(str::to_string);
+
+    with_conn(move |conn| {
+        const SQL: &str = "INSERT INTO publish_attempt (feedback_id, answer, error, created_at) VALUES (?1, ?2, ?3, strftime('%s','now'))";
+
+        conn.execute(SQL, rusqlite::params![feedback_id, answer, error])?;
+
+        Ok(())
+    })
+    .await
+}
 
-    const SQL: &str = "SELECT * FROM product_ai_summary WHERE id = ?1";
+pub async fn select_all_publish_attempts() -> Result<Vec<PublishAttemptRow>> {
+    with_conn(move |conn| {
+        const SQL: &str =
+            "SELECT id, feedback_id, answer, error, created_at FROM publish_attempt ORDER BY id";
 
-    let row = conn
-        .query_one(SQL, &[id], |row| {
-            Ok(ProductAiSummaryRow {
-                id: row.get(0)?,
-                ai_summary: row.get(1)?,
-                created_at: row.get(2)?,
+        let mut stmt = conn.prepare(SQL)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(PublishAttemptRow {
+                    id: row.get(0)?,
+                    feedback_id: row.get(1)?,
+                    answer: row.get(2)?,
+                    error: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShopRow {
+    pub id: String,
+    /// Символ маркетплейса: `oz` или `wb`.
+    pub place: String,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// Сохраняет магазин `id` конфигурации или обновляет его маркетплейс.
+pub async fn upsert_shop(id: &str, place: &str) -> Result<()> {
+    let id = id.to_string();
+    let place = place.to_string();
+
+    with_conn(move |conn| {
+        const SQL: &str = "INSERT INTO shop (id, place, created_at, updated_at) VALUES (?1, ?2, strftime('%s','now'), strftime('%s','now')) ON CONFLICT (id) DO UPDATE SET place = excluded.place, updated_at = excluded.updated_at";
+
+        conn.execute(SQL, [id, place])?;
+
+        Ok(())
+    })
+    .await
+}
+
+pub async fn select_all_shops() -> Result<Vec<ShopRow>> {
+    with_conn(move |conn| {
+        const SQL: &str = "SELECT id, place, created_at, updated_at FROM shop ORDER BY id";
+
+        let mut stmt = conn.prepare(SQL)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(ShopRow {
+                    id: row.get(0)?,
+                    place: row.get(1)?,
+                    created_at: row.get(2)?,
+                    updated_at: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+/// Данные товара магазина, полученные от маркетплейса.
+#[derive(Debug, Serialize)]
+pub struct ProductRow {
+    pub shop_id: String,
+    pub info: ProductFormatInfo,
+    pub updated_at: u64,
+}
+
+fn map_product_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ProductRow> {
+    let info: String = row.get(1)?;
+
+    Ok(ProductRow {
+        shop_id: row.get(0)?,
+        info: serde_json::from_str(&info).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        updated_at: row.get(2)?,
+    })
+}
+
+/// Сохраняет данные товара магазина `shop_id`, заменяя ранее сохранённые.
+pub async fn upsert_product(shop_id: &str, info: &ProductFormatInfo) -> Result<()> {
+    let shop_id = shop_id.to_string();
+    let (id, name) = (info.id.clone(), info.name.clone());
+    let info = serde_json::to_string(info)?;
+
+    with_conn(move |conn| {
+        const SQL: &str = "INSERT OR REPLACE INTO product (shop_id, id, name, info, updated_at) VALUES (?1, ?2, ?3, ?4, strftime('%s','now'))";
+
+        conn.execute(SQL, [shop_id, id, name, info])?;
+
+        Ok(())
+    })
+    .await
+}
+
+pub async fn select_product(shop_id: &str, id: &str) -> Result<Option<ProductRow>> {
+    let shop_id = shop_id.to_string();
+    let id = id.to_string();
+
+    with_conn(move |conn| {
+        const SQL: &str =
+            "SELECT shop_id, info, updated_at FROM product WHERE shop_id = ?1 AND id = ?2";
+
+        let row = conn
+            .query_one(SQL, [shop_id, id], map_product_row)
+            .optional()?;
+
+        Ok(row)
+    })
+    .await
+}
+
+pub async fn select_all_products() -> Result<Vec<ProductRow>> {
+    with_conn(move |conn| {
+        const SQL: &str = "SELECT shop_id, info, updated_at FROM product ORDER BY shop_id, id";
+
+        let mut stmt = conn.prepare(SQL)?;
+        let rows = stmt
+            .query_map([], map_product_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+/// Вызов AI провайдера для записи в журнал.
+#[derive(Debug, Clone, Default)]
+pub struct AiCall {
+    pub shop: String,
+    /// Задача: `summary`, `question` или `review`.
+    pub task: String,
+    pub model: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub latency_ms: u64,
+    /// Стоимость по ценам из конфигурации на момент вызова.
+    pub cost: f64,
+    /// Текст ошибки, если вызов завершился неудачно.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AiCallRow {
+    pub id: i64,
+    pub shop: String,
+    pub task: String,
+    pub model: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub latency_ms: u64,
+    pub cost: f64,
+    pub error: Option<String>,
+    pub created_at: u64,
+}
+
+pub async fn insert_ai_call(call: &AiCall) -> Result<()> {
+    let call = call.clone();
+
+    with_conn(move |conn| {
+        const SQL: &str = "INSERT INTO ai_call (shop, task, model, prompt_tokens, completion_tokens, latency_ms, cost, error, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, strftime('%s','now'))";
+
+        conn.execute(
+            SQL,
+            rusqlite::params![
+                call.shop,
+                call.task,
+                call.model,
+                call.prompt_tokens,
+                call.completion_tokens,
+                call.latency_ms,
+                call.cost,
+                call.error
+            ],
+        )?;
+
+        Ok(())
+    })
+    .await
+}
+
+pub async fn select_all_ai_calls() -> Result<Vec<AiCallRow>> {
+    with_conn(move |conn| {
+        const SQL: &str = "SELECT id, shop, task, model, prompt_tokens, completion_tokens, latency_ms, cost, error, created_at FROM ai_call ORDER BY id";
+
+        let mut stmt = conn.prepare(SQL)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(AiCallRow {
+                    id: row.get(0)?,
+                    shop: row.get(1)?,
+                    task: row.get(2)?,
+                    model: row.get(3)?,
+                    prompt_tokens: row.get(4)?,
+                    completion_tokens: row.get(5)?,
+                    latency_ms: row.get(6)?,
+                    cost: row.get(7)?,
+                    error: row.get(8)?,
+                    created_at: row.get(9)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+/// Суммарная стоимость вызовов AI провайдера, начиная с момента `since` (Unix timestamp).
+pub async fn select_ai_cost_since(since: u64) -> Result<f64> {
+    with_conn(move |conn| {
+        const SQL: &str = "SELECT COALESCE(SUM(cost), 0) FROM ai_call WHERE created_at >= ?1";
+
+        Ok(conn.query_row(SQL, [since], |row| row.get(0))?)
+    })
+    .await
+}
+
+/// Группировка отчёта о вызовах AI провайдера.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageGroup {
+    /// По дням (UTC), `YYYY-MM-DD`.
+    Day,
+    Model,
+    Shop,
+    Task,
+}
+
+impl UsageGroup {
+    fn expr(&self) -> &'static str {
+        match self {
+            Self::Day => "date(created_at, 'unixepoch')",
+            Self::Model => "model",
+            Self::Shop => "shop",
+            Self::Task => "task",
+        }
+    }
+}
+
+/// Строка отчёта о вызовах AI провайдера.
+#[derive(Debug, Serialize)]
+pub struct UsageReportRow {
+    /// Значение группировки: день, модель, магазин или задача.
+    pub key: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+    pub avg_latency_ms: f64,
+}
+
+/// Отчёт о вызовах AI провайдера за период `[from, to]` (Unix timestamp) с группировкой `group`.
+pub async fn select_ai_usage_report(
+    group: UsageGroup,
+    from: u64,
+    to: u64,
+) -> Result<Vec<UsageReportRow>> {
+    with_conn(move |conn| {
+        let key = group.expr();
+        let sql = format!(
+            "SELECT {key}, COUNT(*), COUNT(error), SUM(prompt_tokens), SUM(completion_tokens), SUM(cost), AVG(latency_ms) FROM ai_call WHERE created_at BETWEEN ?1 AND ?2 GROUP BY {key} ORDER BY {key}"
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map([from, to], |row| {
+                Ok(UsageReportRow {
+                    key: row.get(0)?,
+                    calls: row.get(1)?,
+                    errors: row.get(2)?,
+                    prompt_tokens: row.get(3)?,
+                    completion_tokens: row.get(4)?,
+                    cost: row.get(5)?,
+                    avg_latency_ms: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    })
+    .await
+}
+
+#[test]
+fn migrate_test() {
+    let mut conn = Connection::open_in_memory().unwrap();
+
+    // База данных, созданная до появления версий схемы.
+    conn.execute_batch(
+        "CREATE TABLE feedback (id TEXT PRIMARY KEY, kind TEXT NOT NULL, payload TEXT NOT NULL, status TEXT NOT NULL, answer TEXT, error TEXT, attempts INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL);
+INSERT INTO feedback (id, kind, payload, status, created_at, updated_at) VALUES ('wb/1', 'question', '{}', 'received', 0, 0);",
+    )
+    .unwrap();
+
+    migrate(&mut conn).unwrap();
+    migrate(&mut conn).unwrap();
+
+    let versions = conn
+        .prepare("SELECT version FROM schema_version ORDER BY version")
+        .unwrap()
+        .query_map([], |row| row.get::<_, usize>(0))
+        .unwrap()
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(versions, (1..=MIGRATIONS.len()).collect::<Vec<_>>());
+
+    let model: Option<String> = conn
+        .query_row("SELECT model FROM feedback WHERE id = 'wb/1'", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(model, None);
+
+    conn.execute(
+        "INSERT INTO answer (feedback_id, text, source, created_at) VALUES ('wb/1', 'Спасибо!', 'human', 0)",
+        [],
+    )
+    .unwrap();
+
+    conn.execute("INSERT INTO schema_version VALUES (99, 0)", [])
+        .unwrap();
+    assert!(matches!(migrate(&mut conn), Err(Error::Config(_))));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_access_test() {
+    let tasks = (0..32)
+        .map(|i| {
+            tokio::spawn(async move {
+                let id = format!("db-test/{i}");
+                insert_feedback_if_absent(&id, "question", "{}").await?;
+                set_feedback_failed(&id, "test").await?;
+                select_feedback(&id).await
             })
         })
-        .optional()?;
+        .collect::<Vec<_>>();
+
+    for task in tasks {
+        let row = task.await.unwrap().unwrap().unwrap();
+        assert_eq!(row.attempts, 1);
+        assert_eq!(row.error.as_deref(), Some("test"));
+    }
+
+    let mode = with_conn(|conn| {
+        Ok(conn.pragma_query_value(None, "journal_mode", |row| row.get::<_, String>(0))?)
+    })
+    .await
+    .unwrap();
+    assert_eq!(mode, "wal");
+}
+
+#[tokio::test]
+async fn feedback_prefix_test() {
+    for id in ["wb_1/q1", "wbx1/q2", "wb%1/q3"] {
+        insert_feedback_if_absent(id, "question", "{}")
+            .await
+            .unwrap();
+    }
+
+    let ids = |rows: Vec<FeedbackRow>| rows.into_iter().map(|r| r.id).collect::<Vec<_>>();
+
+    let rows = select_unfinished_feedback("wb_1/", &[FeedbackStatus::Received], 5)
+        .await
+        .unwrap();
+    assert_eq!(ids(rows), ["wb_1/q1"]);
+
+    let rows = select_unfinished_feedback("wb%1/", &[FeedbackStatus::Received], 5)
+        .await
+        .unwrap();
+    assert_eq!(ids(rows), ["wb%1/q3"]);
+}
+
+#[tokio::test]
+async fn retry_feedback_test() {
+    let id = "db-retry/q1";
+    insert_feedback_if_absent(id, "question", "{}")
+        .await
+        .unwrap();
+    set_feedback_generated(id, "Ответ", None).await.unwrap();
+    assert!(!retry_feedback(id).await.unwrap());
+
+    assert!(approve_feedback(id, None).await.unwrap());
+    for _ in 0..3 {
+        set_feedback_failed(id, "publish failed").await.unwrap();
+    }
+    assert!(
+        select_unfinished_feedback("db-retry/", &[FeedbackStatus::Approved], 3)
+            .await
+            .unwrap()
+            .is_empty()
+    );
 
-    Ok(row)
+    assert!(retry_feedback(id).await.unwrap());
+    let row = select_feedback(id).await.unwrap().unwrap();
+    assert_eq!((row.attempts, row.error), (0, None));
+    assert_eq!(row.status, FeedbackStatus::Approved);
 }
@@ -1,8 +1,12 @@
 use crate::sellerapi::{OzonSellerApiError, WbSellerApiError};
+use mlua::Error as LuaError;
 use reqwest::Error as ReqwestError;
 use rusqlite::Error as SqliteError;
+use serde_json::Error as JsonError;
 use std::io::Error as StdIoError;
 use std::result::Result as StdResult;
+use std::time::Duration;
+use tera::Error as TeraError;
 use thiserror::Error as ThisError;
 
 pub type Result<T> = StdResult<T, Error>;
@@ -18,15 +22,70 @@ pub enum Error {
     #[error(transparent)]
     Sqlite(#[from] SqliteError),
 
+    #[error(transparent)]
+    Json(#[from] JsonError),
+
+    #[error(transparent)]
+    Tera(#[from] TeraError),
+
+    #[error(transparent)]
+    Lua(#[from] LuaError),
+
     #[error(transparent)]
     OzonSellerApi(#[from] OzonSellerApiError),
 
     #[error(transparent)]
     WbSellerApi(#[from] WbSellerApiError),
 
+    #[error("AiProviderError: {0}.")]
+    AiProvider(String),
+
     #[error("ProductCtxDataError: {0}.")]
     ProductCtxData(String),
 
+    #[error("InvalidAnswerError: {0}.")]
+    InvalidAnswer(String),
+
+    #[error("BudgetError: {0}.")]
+    Budget(String),
+
+    #[error("CassetteError: {0}.")]
+    Cassette(String),
+
+    #[error("ConfigError: {0}.")]
+    Config(String),
+
     #[error("Missing required field: {0}.")]
     MissingRequiredField(String),
 }
+
+impl Error {
+    /// Временная ошибка, после которой запрос имеет смысл повторить:
+    /// таймаут, ошибка соединения, 408, 429 или 5xx.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Io(_) => true,
+            Self::Reqwest(e) => {
+                e.is_timeout()
+                    || e.is_connect()
+                    || e.status().is_some_and(|s| is_transient_status(s.as_u16()))
+            }
+            Self::OzonSellerApi(e) => is_transient_status(e.status_code),
+            Self::WbSellerApi(e) => is_transient_status(e.status_code),
+            _ => false,
+        }
+    }
+
+    /// Пауза перед повтором, запрошенная API (заголовок `X-Ratelimit-Retry` Wildberries).
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::WbSellerApi(e) => e.rate_limit_retry,
+            _ => None,
+        }
+    }
+}
+
+#[inline]
+fn is_transient_status(status_code: u16) -> bool {
+    matches!(status_code, 408 | 429 | 500..=599)
+}
@@ -0,0 +1,155 @@
+use super::{ChatRequest, Message, ModelRouter, RoutedResponse, Task, Tool};
+use crate::error::{Error, Result};
+use std::future::Future;
+
+/// Набор инструментов, которые модель может вызвать в `ModelRouter::chat_with_tools`.
+pub trait ToolHandler {
+    /// Описания инструментов для запроса к модели.
+    fn tools(&self) -> Vec<Tool>;
+
+    /// Выполняет инструмент `name` с аргументами `args` и возвращает результат для модели.
+    fn call(
+        &self,
+        name: &str,
+        args: serde_json::Value,
+    ) -> impl Future<Output = Result<String>> + Send;
+}
+
+impl ModelRouter {
+    /// Запрашивает ответ, выполняя вызовы инструментов `tools`, пока модель не ответит
+    /// текстом, но не более `max_steps` запросов с инструментами. После исчерпания шагов
+    /// модель получает запрос без инструментов (`tool_choice: "none"`).
+    ///
+    /// Все шаги выполняются моделью, ответившей на первый запрос. Ошибка инструмента
+    /// передаётся модели как результат вызова. Возвращает итоговый запрос (диалог
+    /// с результатами вызовов) и ответ модели на него.
+    pub async fn chat_with_tools<H: ToolHandler>(
+        &self,
+        shop: &str,
+        task: Task,
+        r: &ChatRequest,
+        tools: &H,
+        max_steps: u32,
+    ) -> Result<(ChatRequest, RoutedResponse)> {
+        let mut req = ChatRequest {
+            tools: Some(tools.tools()),
+            ..r.clone()
+        };
+
+        for step in 0..=max_steps {
+            if step == max_steps {
+                req.tool_choice = Some("none".into());
+            }
+
+            let mut routed = self.chat(shop, task, &req).await?;
+            let calls = routed
+                .response
+                .choices
+                .first_mut()
+                .map(|c| std::mem::take(&mut c.message.tool_calls))
+                .unwrap_or_default();
+
+            if calls.is_empty() {
+                return Ok((req, routed));
+            }
+            if step == max_steps {
+                break;
+            }
+
+            let content = routed
+                .response
+                .take_message(0)
+                .map(|m| m.content)
+                .unwrap_or_default();
+
+            req.model = routed.model;
+            req.messages.push(Message {
+                tool_calls: calls.clone(),
+                ..Message::assistant(content)
+            });
+
+            for call in calls {
+                let res = match serde_json::from_str(&call.function.arguments) {
+                    Ok(args) => tools.call(&call.function.name, args).await,
+                    Err(e) => Err(e.into()),
+                };
+
+                let content = res.unwrap_or_else(|e| {
+                    eprintln!("Ошибка инструмента {}: {e}", call.function.name);
+                    format!("Ошибка: {e}")
+                });
+                req.messages.push(Message::tool(call.id, content));
+            }
+        }
+
+        Err(Error::AiProvider(format!(
+            "tool calls limit {max_steps} exceeded"
+        )))
+    }
+}
+
+#[tokio::test]
+async fn chat_with_tools_test() {
+    use super::{AiProvider, FunctionCall, MockBackend, Role, ToolCall};
+    use serde_json::json;
+    use std::time::Duration;
+
+    struct Prices;
+
+    impl ToolHandler for Prices {
+        fn tools(&self) -> Vec<Tool> {
+            vec![Tool::function(
+                "get_price",
+                "Цена товара",
+                json!({"type": "object", "properties": {}}),
+            )]
+        }
+
+        async fn call(&self, name: &str, _args: serde_json::Value) -> Result<String> {
+            match name {
+                "get_price" => Ok("100 RUB".into()),
+                _ => Err(Error::AiProvider(format!("unknown tool {name}"))),
+            }
+        }
+    }
+
+    let backend = MockBackend::new(Some("Цена 100 рублей.".into()));
+    let call = |id: &str, name: &str| ToolCall {
+        id: id.into(),
+        kind: "function".into(),
+        function: FunctionCall {
+            name: name.into(),
+            arguments: "{}".into(),
+        },
+    };
+    backend.push_reply(Message {
+        tool_calls: vec![call("1", "get_price"), call("2", "get_stock")],
+        ..Default::default()
+    });
+
+    let router = ModelRouter::new(
+        AiProvider::Mock(backend),
+        Default::default(),
+        Duration::from_secs(60),
+    );
+    let req = super::Conversation::new()
+        .user("Сколько стоит?")
+        .into_request("a");
+
+    let (req, mut res) = router
+        .chat_with_tools("test", Task::Question, &req, &Prices, 3)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        res.response.take_message(0).unwrap().content,
+        "Цена 100 рублей."
+    );
+    assert_eq!(req.messages.len(), 4);
+    assert_eq!(req.messages[2].role, Role::Tool);
+    assert_eq!(req.messages[2].content, "100 RUB");
+    assert_eq!(
+        req.messages[3].content,
+        "Ошибка: AiProviderError: unknown tool get_stock."
+    );
+}
@@ -0,0 +1,126 @@
+use super::LlmBackend;
+use crate::{
+    config::LLmConfig,
+    error::Result,
+    genai::{ChatRequest, ChatResponse, ChatStreamEvent, Choice, Message, Role, Usage},
+};
+use std::{collections::VecDeque, sync::Mutex};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+/// Детерминированная локальная заглушка для проверки без сети: отвечает фиксированным
+/// текстом `reply` или, если он не задан, повторяет последнее сообщение пользователя.
+/// Токеном считается слово. Полученные запросы сохраняются для проверки в тестах.
+///
+/// Сообщения, добавленные `push_reply` (например, с вызовами инструментов),
+/// отправляются по одному в порядке добавления раньше `reply`.
+#[derive(Default)]
+pub struct MockBackend {
+    pub reply: Option<String>,
+    replies: Mutex<VecDeque<Message>>,
+    requests: Mutex<Vec<ChatRequest>>,
+}
+
+impl MockBackend {
+    pub fn new(reply: Option<String>) -> Self {
+        Self {
+            reply,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_config(cfg: &LLmConfig) -> Self {
+        Self::new(cfg.mock_reply.clone())
+    }
+
+    /// Запросы, полученные заглушкой, в порядке поступления.
+    pub fn requests(&self) -> Vec<ChatRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Добавляет сообщение в очередь ответов.
+    pub fn push_reply(&self, msg: Message) {
+        self.replies.lock().unwrap().push_back(msg);
+    }
+
+    fn answer(&self, r: &ChatRequest) -> (Message, Usage) {
+        self.requests.lock().unwrap().push(r.clone());
+
+        if let Some(msg) = self.replies.lock().unwrap().pop_front() {
+            let usage = usage(r, &msg.content);
+            return (msg, usage);
+        }
+
+        let content = self.reply.clone().unwrap_or_else(|| {
+            r.messages
+                .iter()
+                .rev()
+                .find(|m| m.role == Role::User)
+                .map(|m| m.content.clone())
+                .unwrap_or_default()
+        });
+
+        let usage = usage(r, &content);
+        (Message::assistant(content), usage)
+    }
+}
+
+fn usage(r: &ChatRequest, content: &str) -> Usage {
+    let prompt_tokens = r
+        .messages
+        .iter()
+        .map(|m| m.content.split_whitespace().count() as u32)
+        .sum();
+    let completion_tokens = content.split_whitespace().count() as u32;
+
+    Usage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+    }
+}
+
+impl LlmBackend for MockBackend {
+    async fn chat(&self, r: &ChatRequest) -> Result<ChatResponse> {
+        let (message, usage) = self.answer(r);
+        let finish_reason = match message.tool_calls.is_empty() {
+            true => "stop",
+            false => "tool_calls",
+        };
+
+        Ok(ChatResponse {
+            id: "mock".into(),
+            object: "chat.completion".into(),
+            created: 0,
+            model: r.model.clone(),
+            choices: vec![Choice {
+                index: 0,
+                message,
+                finish_reason: Some(finish_reason.into()),
+            }],
+            usage: Some(usage),
+        })
+    }
+
+    /// Отправляет ответ по словам, сохраняя пробелы между ними.
+    async fn chat_stream(
+        &self,
+        r: &ChatRequest,
+    ) -> Result<UnboundedReceiver<Result<ChatStreamEvent>>> {
+        let (message, usage) = self.answer(r);
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        for word in message.content.split_inclusive(' ') {
+            let _ = tx.send(Ok(ChatStreamEvent::Delta {
+                index: 0,
+                content: word.to_string(),
+            }));
+        }
+        let _ = tx.send(Ok(ChatStreamEvent::Finish {
+            index: 0,
+            reason: "stop".into(),
+        }));
+        let _ = tx.send(Ok(ChatStreamEvent::Usage(usage)));
+
+        Ok(rx)
+    }
+}
@@ -0,0 +1,120 @@
+mod mock;
+mod ollama;
+mod openai;
+
+pub use mock::*;
+pub use ollama::*;
+pub use openai::*;
+
+use crate::{
+    error::{Error, Result},
+    genai::{ChatRequest, ChatResponse, ChatStreamEvent},
+};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+/// API генеративной модели, к которому обращается `AiProvider`.
+pub trait LlmBackend {
+    /// Запрашивает ответ модели целиком.
+    fn chat(&self, r: &ChatRequest) -> impl Future<Output = Result<ChatResponse>> + Send;
+
+    /// Запрашивает ответ в потоковом режиме и возвращает канал событий.
+    /// Канал закрывается после завершения генерации или первой ошибки.
+    fn chat_stream(
+        &self,
+        r: &ChatRequest,
+    ) -> impl Future<Output = Result<UnboundedReceiver<Result<ChatStreamEvent>>>> + Send;
+}
+
+/// Результат разбора строки потокового ответа.
+enum StreamLine {
+    /// События строки, поток продолжается.
+    Events(Vec<ChatStreamEvent>),
+    /// Завершающая строка потока и её события.
+    Done(Vec<ChatStreamEvent>),
+}
+
+/// Читает тело потокового ответа построчно и отправляет события, полученные из строк
+/// функцией `parse_line`, в канал. Тело ответа, закончившееся до завершающей строки
+/// (`StreamLine::Done`), считается оборванным: в канал отправляется ошибка.
+/// `idle_timeout` ограничивает паузу между фрагментами тела ответа.
+fn spawn_line_stream<F>(
+    mut response: reqwest::Response,
+    idle_timeout: Duration,
+    mut parse_line: F,
+) -> UnboundedReceiver<Result<ChatStreamEvent>>
+where
+    F: FnMut(&str) -> Result<StreamLine> + Send + 'static,
+{
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let mut lines = LineBuffer::default();
+
+        loop {
+            let chunk = match tokio::time::timeout(idle_timeout, response.chunk()).await {
+                Ok(Ok(Some(chunk))) => chunk,
+                Ok(Ok(None)) => {
+                    let _ = tx.send(Err(Error::AiProvider(
+                        "stream ended before completion".into(),
+                    )));
+                    return;
+                }
+                Ok(Err(e)) => {
+                    let _ = tx.send(Err(e.into()));
+                    return;
+                }
+                Err(_) => {
+                    let _ = tx.send(Err(Error::AiProvider("stream chunk timed out".into())));
+                    return;
+                }
+            };
+
+            for line in lines.push(&chunk) {
+                let (events, done) = match parse_line(&line) {
+                    Ok(StreamLine::Events(events)) => (events, false),
+                    Ok(StreamLine::Done(events)) => (events, true),
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                        return;
+                    }
+                };
+
+                for event in events {
+                    if tx.send(Ok(event)).is_err() {
+                        return;
+                    }
+                }
+                if done {
+                    return;
+                }
+            }
+        }
+    });
+
+    rx
+}
+
+/// Буфер построчного разбора тела ответа, приходящего фрагментами.
+/// Строка может быть разделена между фрагментами, в том числе внутри символа UTF-8.
+#[derive(Default)]
+struct LineBuffer {
+    buf: Vec<u8>,
+}
+
+impl LineBuffer {
+    /// Добавляет фрагмент и возвращает полученные полные строки без `\r\n`.
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buf.extend_from_slice(bytes);
+
+        let mut res = Vec::new();
+
+        while let Some(pos) = self.buf.iter().position(|&b| b == b'\n') {
+            let line = self.buf.drain(..=pos).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            res.push(line.trim_end_matches(['\r', '\n']).to_string());
+        }
+
+        res
+    }
+}
@@ -0,0 +1,335 @@
+use super::{LlmBackend, StreamLine, spawn_line_stream};
+use crate::{
+    config::LLmConfig,
+    error::{Error, Result},
+    genai::{
+        ChatRequest, ChatResponse, ChatStreamEvent, Choice, FunctionCall, Message, ResponseFormat,
+        Role, Tool, ToolCall, Usage,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Нативный API Ollama `/api/chat`.
+pub struct OllamaBackend {
+    pub base_url: String,
+    /// Токен для Ollama за обратным прокси с авторизацией; пустой — без авторизации.
+    pub api_key: String,
+    /// Общий HTTP клиент (см. `HttpConfig::client`).
+    pub http: reqwest::Client,
+    pub timeout: Duration,
+}
+
+/// Запрос `/api/chat`.
+#[derive(Serialize)]
+struct OllamaChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<OllamaRequestMessage<'a>>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<&'a [Tool]>,
+    /// `"json"` или JSON схема ответа.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "OllamaOptions::is_empty")]
+    options: OllamaOptions,
+}
+
+/// Сообщение запроса: аргументы вызовов инструментов передаются объектом,
+/// а результат вызова — с именем инструмента вместо идентификатора вызова.
+#[derive(Serialize)]
+struct OllamaRequestMessage<'a> {
+    role: Role,
+    content: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<OllamaToolCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_name: Option<&'a str>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct OllamaToolCall {
+    function: OllamaFunctionCall,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct OllamaFunctionCall {
+    name: String,
+    arguments: serde_json::Value,
+}
+
+/// Параметры генерации Ollama.
+#[derive(Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    /// Максимальное количество токенов в ответе.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+}
+
+impl OllamaOptions {
+    fn is_empty(&self) -> bool {
+        self.temperature.is_none() && self.num_predict.is_none()
+    }
+}
+
+/// Ответ `/api/chat` или фрагмент потокового ответа (NDJSON).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct OllamaChatResponse {
+    model: String,
+    created_at: String,
+    message: OllamaMessage,
+    done: bool,
+    done_reason: Option<String>,
+    /// Количество токенов в запросе.
+    prompt_eval_count: Option<u32>,
+    /// Количество токенов в ответе.
+    eval_count: Option<u32>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct OllamaMessage {
+    content: String,
+    /// Рассуждения модели (модели с `think`).
+    thinking: Option<String>,
+    tool_calls: Vec<OllamaToolCall>,
+}
+
+impl OllamaChatResponse {
+    fn usage(&self) -> Option<Usage> {
+        let (prompt_tokens, completion_tokens) = (self.prompt_eval_count?, self.eval_count?);
+        Some(Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        })
+    }
+
+    fn into_chat_response(self) -> ChatResponse {
+        let usage = self.usage();
+        let created = OffsetDateTime::parse(&self.created_at, &Rfc3339)
+            .map_or(0, |t| t.unix_timestamp().max(0) as u64);
+
+        // Ollama не присваивает вызовам идентификаторы.
+        let tool_calls = self
+            .message
+            .tool_calls
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| ToolCall {
+                id: format!("call_{i}"),
+                kind: "function".into(),
+                function: FunctionCall {
+                    name: c.function.name,
+                    arguments: c.function.arguments.to_string(),
+                },
+            })
+            .collect::<Vec<_>>();
+        let finish_reason = match tool_calls.is_empty() {
+            true => self.done_reason,
+            false => Some("tool_calls".into()),
+        };
+
+        ChatResponse {
+            id: format!("ollama-{created}"),
+            object: "chat.completion".into(),
+            created,
+            model: self.model,
+            choices: vec![Choice {
+                index: 0,
+                message: Message {
+                    tool_calls,
+                    ..Message::new(Role::Assistant, self.message.content)
+                },
+                finish_reason,
+            }],
+            usage,
+        }
+    }
+
+    fn into_events(self) -> Vec<ChatStreamEvent> {
+        let mut events = Vec::new();
+
+        if let Some(content) = self.message.thinking.clone().filter(|v| !v.is_empty()) {
+            events.push(ChatStreamEvent::Reasoning { index: 0, content });
+        }
+        if !self.message.content.is_empty() {
+            events.push(ChatStreamEvent::Delta {
+                index: 0,
+                content: self.message.content.clone(),
+            });
+        }
+        if self.done {
+            let usage = self.usage();
+            events.push(ChatStreamEvent::Finish {
+                index: 0,
+                reason: self.done_reason.unwrap_or_else(|| "stop".into()),
+            });
+            events.extend(usage.map(ChatStreamEvent::Usage));
+        }
+
+        events
+    }
+}
+
+impl OllamaBackend {
+    pub fn from_config(cfg: &LLmConfig, http: reqwest::Client) -> Self {
+        Self {
+            base_url: cfg.base_url.trim_end_matches('/').to_string(),
+            api_key: cfg.api_key.clone(),
+            http,
+            timeout: cfg.timeout(),
+        }
+    }
+
+    fn request(&self, r: &ChatRequest, stream: bool) -> Result<reqwest::RequestBuilder> {
+        // Ollama не поддерживает `tool_choice`: при "none" инструменты не передаются.
+        let tools = r
+            .tools
+            .as_deref()
+            .filter(|_| r.tool_choice.as_deref() != Some("none"));
+
+        let payload = OllamaChatRequest {
+            model: &r.model,
+            messages: r.messages.iter().map(|m| request_message(m, r)).collect(),
+            stream,
+            tools,
+            format: match r.response_format {
+                Some(ResponseFormat::JsonObject) => Some("json".into()),
+                Some(ResponseFormat::JsonSchema { ref json_schema }) => {
+                    Some(json_schema.schema.clone())
+                }
+                Some(ResponseFormat::Text) | None => None,
+            },
+            options: OllamaOptions {
+                temperature: r.temperature,
+                num_predict: r.max_tokens,
+            },
+        };
+
+        let mut request = self
+            .http
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&payload);
+        if !self.api_key.is_empty() {
+            request = request.bearer_auth(&self.api_key);
+        }
+
+        Ok(request)
+    }
+}
+
+impl LlmBackend for OllamaBackend {
+    async fn chat(&self, r: &ChatRequest) -> Result<ChatResponse> {
+        let res = self
+            .request(r, false)?
+            .timeout(self.timeout)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<OllamaChatResponse>()
+            .await?;
+
+        if let Some(error) = res.error {
+            return Err(Error::AiProvider(error));
+        }
+
+        Ok(res.into_chat_response())
+    }
+
+    /// Запрашивает ответ в потоковом режиме: Ollama возвращает по одному JSON объекту
+    /// на строку, последний содержит `done: true` и статистику токенов.
+    async fn chat_stream(
+        &self,
+        r: &ChatRequest,
+    ) -> Result<UnboundedReceiver<Result<ChatStreamEvent>>> {
+        let request = self.request(r, true)?.send();
+
+        let response = tokio::time::timeout(self.timeout, request)
+            .await
+            .map_err(|_| Error::AiProvider("response headers timed out".into()))??
+            .error_for_status()?;
+
+        Ok(spawn_line_stream(response, self.timeout, parse_ndjson_line))
+    }
+}
+
+fn request_message<'a>(m: &'a Message, r: &'a ChatRequest) -> OllamaRequestMessage<'a> {
+    let tool_name = m.tool_call_id.as_deref().and_then(|id| {
+        r.messages
+            .iter()
+            .flat_map(|m| &m.tool_calls)
+            .find(|c| c.id == id)
+            .map(|c| c.function.name.as_str())
+    });
+
+    OllamaRequestMessage {
+        role: m.role,
+        content: &m.content,
+        tool_calls: m
+            .tool_calls
+            .iter()
+            .map(|c| OllamaToolCall {
+                function: OllamaFunctionCall {
+                    name: c.function.name.clone(),
+                    arguments: serde_json::from_str(&c.function.arguments)
+                        .unwrap_or_else(|_| serde_json::json!({})),
+                },
+            })
+            .collect(),
+        tool_name,
+    }
+}
+
+/// Разбирает строку потокового ответа Ollama. Пустые строки пропускаются,
+/// строка с `done: true` завершает поток.
+fn parse_ndjson_line(line: &str) -> Result<StreamLine> {
+    if line.trim().is_empty() {
+        return Ok(StreamLine::Events(Vec::new()));
+    }
+
+    let res = serde_json::from_str::<OllamaChatResponse>(line)?;
+
+    if let Some(error) = res.error {
+        return Err(Error::AiProvider(error));
+    }
+
+    Ok(match res.done {
+        true => StreamLine::Done(res.into_events()),
+        false => StreamLine::Events(res.into_events()),
+    })
+}
+
+#[test]
+fn ollama_response_test() {
+    let res = serde_json::from_str::<OllamaChatResponse>(
+        r#"{"model":"qwen3:8b","created_at":"2025-06-01T10:00:00.000000Z","message":{"role":"assistant","content":"Здравствуйте!"},"done":true,"done_reason":"stop","prompt_eval_count":20,"eval_count":5}"#,
+    )
+    .unwrap()
+    .into_chat_response();
+
+    assert_eq!(res.created, 1748772000);
+    assert_eq!(res.choices[0].message.content, "Здравствуйте!");
+    assert_eq!(res.usage.unwrap().total_tokens, 25);
+
+    let Ok(StreamLine::Done(events)) = parse_ndjson_line(
+        r#"{"model":"qwen3:8b","message":{"role":"assistant","content":""},"done":true,"done_reason":"stop","prompt_eval_count":20,"eval_count":5}"#,
+    ) else {
+        panic!("expected final stream line");
+    };
+    assert!(matches!(
+        &events[..],
+        [ChatStreamEvent::Finish { .. }, ChatStreamEvent::Usage(_)]
+    ));
+
+    let error = parse_ndjson_line(r#"{"error":"model \"x\" not found"}"#);
+    assert!(matches!(error, Err(Error::AiProvider(m)) if m == "model \"x\" not found"));
+}
@@ -0,0 +1,195 @@
+use super::{LlmBackend, StreamLine, spawn_line_stream};
+use crate::{
+    config::LLmConfig,
+    error::{Error, Result},
+    genai::{ChatChunk, ChatRequest, ChatResponse, ChatStreamEvent},
+};
+use hyper::Method;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// OpenAI-совместимый API `/chat/completions` (OpenRouter, vLLM и т.п.).
+pub struct OpenAiBackend {
+    pub base_url: String,
+    pub api_key: String,
+    /// Общий HTTP клиент (см. `HttpConfig::client`).
+    pub http: reqwest::Client,
+    pub timeout: Duration,
+}
+
+impl OpenAiBackend {
+    pub fn new(base_url: &str, api_key: &str, http: reqwest::Client) -> Self {
+        Self {
+            base_url: base_url.into(),
+            api_key: api_key.into(),
+            http,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn from_config(cfg: &LLmConfig, http: reqwest::Client) -> Self {
+        let mut backend = Self::new(&cfg.base_url, &cfg.api_key, http);
+        backend.timeout = cfg.timeout();
+        backend
+    }
+
+    async fn call_api<T: DeserializeOwned, R: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        payload: &R,
+    ) -> Result<T> {
+        let url = format!("{}{}", self.base_url, path);
+
+        // Ограничение частоты запросов (429) не повторяется здесь: запрос переходит
+        // к следующей модели в `ModelRouter`.
+        let value = self
+            .http
+            .request(method, &url)
+            .timeout(self.timeout)
+            .bearer_auth(&self.api_key)
+            .json(payload)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<serde_json::Value>()
+            .await?;
+
+        if let Some(e) = provider_error(&value) {
+            return Err(e);
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl LlmBackend for OpenAiBackend {
+    async fn chat(&self, r: &ChatRequest) -> Result<ChatResponse> {
+        self.call_api(Method::POST, "/chat/completions", r).await
+    }
+
+    /// Запрашивает ответ в потоковом режиме (`stream: true`, Server-Sent Events).
+    /// `timeout` ограничивает ожидание заголовков ответа и паузу между фрагментами,
+    /// а не общее время генерации. Канал закрывается после `data: [DONE]` или первой ошибки.
+    async fn chat_stream(
+        &self,
+        r: &ChatRequest,
+    ) -> Result<UnboundedReceiver<Result<ChatStreamEvent>>> {
+        let url = format!("{}/chat/completions", self.base_url);
+
+        let mut payload = serde_json::to_value(r)?;
+        payload["stream"] = json!(true);
+        // Без `include_usage` OpenAI и vLLM не присылают токены, и вызов не учитывается в расходах.
+        payload["stream_options"] = json!({ "include_usage": true });
+
+        let request = self
+            .http
+            .post(&url)
+            .bearer_auth(&self.api_key)
+            .header("Accept", "text/event-stream")
+            .json(&payload)
+            .send();
+
+        let response = tokio::time::timeout(self.timeout, request)
+            .await
+            .map_err(|_| Error::AiProvider("response headers timed out".into()))??
+            .error_for_status()?;
+
+        Ok(spawn_line_stream(response, self.timeout, parse_sse_line))
+    }
+}
+
+/// Разбирает строку потока Server-Sent Events. Событием считается только поле `data:`;
+/// комментарии (`: ...`) и прочие поля пропускаются. `data: [DONE]` завершает поток.
+fn parse_sse_line(line: &str) -> Result<StreamLine> {
+    let Some(data) = line.strip_prefix("data:").map(str::trim_start) else {
+        return Ok(StreamLine::Events(Vec::new()));
+    };
+
+    if data == "[DONE]" {
+        return Ok(StreamLine::Done(Vec::new()));
+    }
+
+    parse_stream_data(data).map(StreamLine::Events)
+}
+
+/// Разбирает поле `data:` события SSE: фрагмент ответа или сообщение об ошибке провайдера.
+fn parse_stream_data(data: &str) -> Result<Vec<ChatStreamEvent>> {
+    let value = serde_json::from_str::<serde_json::Value>(data)?;
+
+    if let Some(e) = provider_error(&value) {
+        return Err(e);
+    }
+
+    Ok(serde_json::from_value::<ChatChunk>(value)?.into_events())
+}
+
+/// Ошибка, переданная провайдером в теле ответа с кодом 200 (`{"error": {"message": ...}}`).
+fn provider_error(value: &serde_json::Value) -> Option<Error> {
+    let error = value.get("error")?;
+    let message = error
+        .get("message")
+        .and_then(|v| v.as_str())
+        .map_or_else(|| error.to_string(), str::to_string);
+
+    Some(Error::AiProvider(message))
+}
+
+#[test]
+fn sse_parser_test() {
+    let mut lines = super::LineBuffer::default();
+
+    let comment = lines.push(b": OPENROUTER PROCESSING\n\n");
+    assert!(
+        comment
+            .iter()
+            .all(|l| matches!(parse_sse_line(l), Ok(StreamLine::Events(ref e)) if e.is_empty()))
+    );
+    assert!(
+        lines
+            .push(b"data: {\"choices\":[{\"delta\":{\"content\":\"\xd0\x9f")
+            .is_empty()
+    );
+
+    let data = lines.push(b"\xd1\x80\xd0\xb8\"}}]}\r\n\ndata: [DONE]\n");
+    assert_eq!(data.len(), 3);
+    assert!(matches!(parse_sse_line(&data[2]), Ok(StreamLine::Done(_))));
+
+    let Ok(StreamLine::Events(events)) = parse_sse_line(&data[0]) else {
+        panic!("expected stream events");
+    };
+    assert!(
+        matches!(&events[..], [ChatStreamEvent::Delta { index: 0, content }] if content == "При")
+    );
+
+    let error = parse_stream_data(r#"{"error":{"message":"Rate limit exceeded","code":429}}"#);
+    assert!(matches!(error, Err(Error::AiProvider(m)) if m == "Rate limit exceeded"));
+}
+
+#[tokio::test]
+async fn sse_stream_terminator_test() {
+    let chunk = "data: {\"choices\":[{\"delta\":{\"content\":\"При\"}}]}\n\n";
+    let stream = |body: String| {
+        let response = reqwest::Response::from(hyper::Response::new(body.into_bytes()));
+        spawn_line_stream(response, std::time::Duration::from_secs(1), parse_sse_line)
+    };
+
+    let mut rx = stream(format!("{chunk}data: [DONE]\n\n"));
+    assert!(matches!(
+        rx.recv().await,
+        Some(Ok(ChatStreamEvent::Delta { .. }))
+    ));
+    assert!(rx.recv().await.is_none());
+
+    // Ответ, оборванный до `data: [DONE]`.
+    let mut rx = stream(chunk.to_string());
+    assert!(matches!(
+        rx.recv().await,
+        Some(Ok(ChatStreamEvent::Delta { .. }))
+    ));
+    assert!(matches!(rx.recv().await, Some(Err(Error::AiProvider(_)))));
+}
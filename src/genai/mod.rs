@@ -1,5 +1,17 @@
+mod agent;
+mod backend;
+mod conversation;
 mod models;
+mod postprocess;
 mod provider;
+mod router;
+mod structured;
 
+pub use agent::*;
+pub use backend::*;
+pub use conversation::*;
 pub use models::*;
+pub use postprocess::*;
 pub use provider::*;
+pub use router::*;
+pub use structured::*;
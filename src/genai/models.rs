@@ -1,33 +1,164 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-pub const SYSTEM_ROLE: &str = "system";
-pub const USER_ROLE: &str = "system";
-pub const ASSISTANT_ROLE: &str = "system";
+/// Роль отправителя сообщения
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Инструкции для модели
+    System,
+    /// Сообщение пользователя
+    User,
+    /// Ответ модели
+    #[default]
+    Assistant,
+    /// Результат вызова инструмента
+    Tool,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Tool => "tool",
+        }
+    }
+}
 
 /// Сообщение в диалоге с LLM
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Message {
-    /// Роль отправителя:
-    /// - "system" — инструкции для модели
-    /// - "user" — сообщение пользователя
-    /// - "assistant" — ответ модели
-    pub role: String,
+    /// Роль отправителя
+    pub role: Role,
 
     /// Текстовое содержимое сообщения
+    /// (`null` в ответе модели, вызывающей инструменты, считается пустой строкой)
+    #[serde(default, deserialize_with = "null_as_default")]
     pub content: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Идентификатор вызова инструмента, на который отвечает сообщение с ролью `tool`
+    pub tool_call_id: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Вызовы инструментов, запрошенные моделью
+    pub tool_calls: Vec<ToolCall>,
 }
 
 impl Message {
-    pub fn new_user_msg<T: Into<String>>(content: T) -> Self {
+    pub fn new<T: Into<String>>(role: Role, content: T) -> Self {
         Self {
-            role: USER_ROLE.to_string(),
+            role,
             content: content.into(),
+            tool_call_id: None,
+            tool_calls: Vec::new(),
+        }
+    }
+
+    /// Инструкции для модели.
+    #[inline]
+    pub fn system<T: Into<String>>(content: T) -> Self {
+        Self::new(Role::System, content)
+    }
+
+    /// Сообщение пользователя.
+    #[inline]
+    pub fn user<T: Into<String>>(content: T) -> Self {
+        Self::new(Role::User, content)
+    }
+
+    /// Ответ модели.
+    #[inline]
+    pub fn assistant<T: Into<String>>(content: T) -> Self {
+        Self::new(Role::Assistant, content)
+    }
+
+    /// Результат вызова инструмента `tool_call_id`.
+    pub fn tool<I: Into<String>, T: Into<String>>(tool_call_id: I, content: T) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::new(Role::Tool, content)
+        }
+    }
+}
+
+/// Вызов инструмента, запрошенный моделью
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Идентификатор вызова, который указывается в ответе с ролью `tool`
+    pub id: String,
+
+    /// Тип инструмента (всегда "function")
+    #[serde(rename = "type", default = "function_type")]
+    pub kind: String,
+
+    /// Вызываемая функция и её аргументы
+    pub function: FunctionCall,
+}
+
+/// Вызов функции
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct FunctionCall {
+    /// Имя функции
+    pub name: String,
+
+    /// Аргументы в виде строки с JSON объектом
+    #[serde(default)]
+    pub arguments: String,
+}
+
+/// Инструмент, который модель может вызвать (OpenAI-style `tools`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tool {
+    /// Тип инструмента (всегда "function")
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    /// Описание функции
+    pub function: FunctionDef,
+}
+
+/// Описание функции, доступной модели
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionDef {
+    /// Имя функции (латиница, цифры, `_` и `-`)
+    pub name: String,
+
+    /// Назначение функции: по нему модель решает, когда её вызвать
+    pub description: String,
+
+    /// JSON схема аргументов
+    pub parameters: serde_json::Value,
+}
+
+impl Tool {
+    pub fn function(name: &str, description: &str, parameters: serde_json::Value) -> Self {
+        Self {
+            kind: function_type(),
+            function: FunctionDef {
+                name: name.to_string(),
+                description: description.to_string(),
+                parameters,
+            },
         }
     }
 }
 
+fn function_type() -> String {
+    "function".into()
+}
+
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
 /// Запрос к модели генерации текста/чата
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ChatRequest {
     /// Имя модели (например: "gpt-4o", "claude-3-opus", "gemini-1.5-pro")
     pub model: String,
@@ -49,6 +180,57 @@ pub struct ChatRequest {
     /// Включить ли потоковую передачу (streaming)
     /// true = ответ приходит частями, false = цельный ответ
     pub stream: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Формат ответа: свободный текст, JSON объект или JSON по схеме
+    pub response_format: Option<ResponseFormat>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Инструменты, которые модель может вызвать
+    pub tools: Option<Vec<Tool>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Выбор инструмента: "auto", "none" или "required"
+    pub tool_choice: Option<String>,
+}
+
+/// Формат ответа модели (OpenAI-style `response_format`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Свободный текст
+    Text,
+    /// Любой корректный JSON объект
+    JsonObject,
+    /// JSON, соответствующий схеме
+    JsonSchema { json_schema: JsonSchema },
+}
+
+/// JSON схема ответа
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonSchema {
+    /// Имя схемы (латиница, цифры, `_` и `-`)
+    pub name: String,
+
+    /// Схема в формате JSON Schema
+    pub schema: serde_json::Value,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Строгое соответствие схеме (поддерживается не всеми моделями)
+    pub strict: Option<bool>,
+}
+
+impl ResponseFormat {
+    /// JSON по схеме `schema` с именем `name` в строгом режиме.
+    pub fn json_schema(name: &str, schema: serde_json::Value) -> Self {
+        Self::JsonSchema {
+            json_schema: JsonSchema {
+                name: name.to_string(),
+                schema,
+                strict: Some(true),
+            },
+        }
+    }
 }
 
 /// Один вариант ответа модели
@@ -110,3 +292,100 @@ impl ChatResponse {
             .map(|c| std::mem::take(&mut c.message))
     }
 }
+
+/// Фрагмент потокового ответа модели (OpenAI-style `chat.completion.chunk`)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatChunk {
+    /// Уникальный идентификатор запроса
+    #[serde(default)]
+    pub id: String,
+
+    /// Использованная модель
+    #[serde(default)]
+    pub model: String,
+
+    /// Изменения вариантов ответа
+    #[serde(default)]
+    pub choices: Vec<ChunkChoice>,
+
+    /// Статистика использования токенов (обычно только в последнем фрагменте)
+    pub usage: Option<Usage>,
+}
+
+/// Изменение одного варианта ответа в потоковом режиме
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChunkChoice {
+    /// Индекс варианта
+    #[serde(default)]
+    pub index: u32,
+
+    /// Добавленный фрагмент сообщения
+    #[serde(default)]
+    pub delta: Delta,
+
+    /// Причина завершения генерации (в последнем фрагменте варианта)
+    pub finish_reason: Option<String>,
+}
+
+/// Добавленный фрагмент сообщения
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Delta {
+    /// Роль отправителя (обычно только в первом фрагменте)
+    pub role: Option<Role>,
+
+    /// Фрагмент текста ответа
+    pub content: Option<String>,
+
+    /// Фрагмент рассуждений модели (например, DeepSeek-R1 через OpenRouter)
+    pub reasoning: Option<String>,
+}
+
+/// Событие потокового ответа модели
+#[derive(Debug)]
+pub enum ChatStreamEvent {
+    /// Очередной фрагмент текста ответа варианта `index`
+    Delta { index: u32, content: String },
+
+    /// Очередной фрагмент рассуждений модели
+    Reasoning { index: u32, content: String },
+
+    /// Генерация варианта `index` завершена
+    Finish { index: u32, reason: String },
+
+    /// Статистика использования токенов
+    Usage(Usage),
+}
+
+impl ChatChunk {
+    /// Раскладывает фрагмент на события в порядке следования.
+    pub fn into_events(self) -> Vec<ChatStreamEvent> {
+        let mut events = Vec::new();
+
+        for c in self.choices {
+            if let Some(content) = c.delta.reasoning.filter(|v| !v.is_empty()) {
+                events.push(ChatStreamEvent::Reasoning {
+                    index: c.index,
+                    content,
+                });
+            }
+            if let Some(content) = c.delta.content.filter(|v| !v.is_empty()) {
+                events.push(ChatStreamEvent::Delta {
+                    index: c.index,
+                    content,
+                });
+            }
+            if let Some(reason) = c.finish_reason {
+                events.push(ChatStreamEvent::Finish {
+                    index: c.index,
+                    reason,
+                });
+            }
+        }
+
+        if let Some(usage) = self.usage {
+            events.push(ChatStreamEvent::Usage(usage));
+        }
+
+        events
+    }
+}
@@ -0,0 +1,218 @@
+use crate::error::{Error, Result};
+
+/// Вступления, которыми модели начинают ответ: `"Ответ: ..."`, `"Готовый ответ на отзыв: ..."`.
+const PREAMBLES: &[&str] = &[
+    "ответ",
+    "ответ покупателю",
+    "ответ на вопрос",
+    "ответ на вопрос покупателя",
+    "ответ на отзыв",
+    "ответ на отзыв покупателя",
+    "готовый ответ",
+    "готовый ответ на вопрос",
+    "готовый ответ на вопрос покупателя",
+    "готовый ответ на отзыв",
+    "готовый ответ на отзыв покупателя",
+    "answer",
+];
+
+/// Парные кавычки, которыми модели обрамляют ответ целиком.
+const QUOTES: &[(char, char)] = &[('"', '"'), ('«', '»'), ('“', '”'), ('„', '“'), ('\'', '\'')];
+
+/// Готовит ответ модели к публикации на маркетплейсе: удаляет рассуждения,
+/// markdown разметку, вступления и обрамляющие кавычки, а затем сокращает текст
+/// до `max_chars` символов по границе предложения.
+/// Возвращает ошибку, если после обработки текст пуст.
+pub fn prepare_answer(text: &str, max_chars: usize) -> Result<String> {
+    let text = truncate(&clean_answer(text), max_chars);
+
+    if text.is_empty() {
+        return Err(Error::InvalidAnswer(
+            "answer is empty after post-processing".into(),
+        ));
+    }
+
+    Ok(text)
+}
+
+/// Удаляет из ответа модели рассуждения, markdown разметку, вступления и обрамляющие кавычки.
+pub fn clean_answer(text: &str) -> String {
+    let text = strip_reasoning(text);
+
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let line = strip_markdown_line(line.trim());
+        // Несколько пустых строк подряд сводятся к одной.
+        if line.is_empty() && lines.last().is_none_or(String::is_empty) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+
+    let text = lines.join("\n");
+    let text = strip_preamble(&text);
+
+    strip_quotes(text.trim()).trim().to_string()
+}
+
+/// Удаляет блоки рассуждений `<think>...</think>`. Если провайдер удалил открывающий тег,
+/// рассуждениями считается всё до `</think>`; незакрытый блок удаляется до конца текста.
+pub fn strip_reasoning(text: &str) -> String {
+    const OPEN: &str = "<think>";
+    const CLOSE: &str = "</think>";
+
+    // Только ASCII символы меняют регистр, поэтому позиции в `lower` совпадают с `text`.
+    let lower = text.to_ascii_lowercase();
+    let mut pos = 0;
+
+    if let Some(close) = lower.find(CLOSE)
+        && !lower[..close].contains(OPEN)
+    {
+        pos = close + CLOSE.len();
+    }
+
+    let mut res = String::with_capacity(text.len() - pos);
+    while let Some(start) = lower[pos..].find(OPEN) {
+        res.push_str(&text[pos..pos + start]);
+
+        match lower[pos + start..].find(CLOSE) {
+            Some(end) => pos += start + end + CLOSE.len(),
+            None => return res,
+        }
+    }
+    res.push_str(&text[pos..]);
+
+    res
+}
+
+/// Удаляет markdown разметку строки: заголовки, цитаты, выделение, код и ссылки.
+/// Маркеры списков заменяются на `-`, разделители удаляются.
+fn strip_markdown_line(line: &str) -> String {
+    if line.len() >= 3 && line.chars().all(|c| matches!(c, '-' | '*' | '_' | ' ')) {
+        return String::new();
+    }
+
+    let mut line = line.trim_start_matches('>').trim_start();
+
+    let heading = line.trim_start_matches('#');
+    if heading.len() < line.len() && heading.starts_with(' ') {
+        line = heading.trim_start();
+    }
+
+    let (bullet, line) = match line.strip_prefix("* ").or_else(|| line.strip_prefix("+ ")) {
+        Some(rest) => ("- ", rest),
+        None => ("", line),
+    };
+
+    let mut res = String::with_capacity(line.len());
+    res.push_str(bullet);
+    res.push_str(&strip_links(line).replace(['*', '`'], "").replace("__", ""));
+
+    res
+}
+
+/// Заменяет ссылки `[текст](url)` их текстом.
+fn strip_links(line: &str) -> String {
+    let mut res = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(open) = rest.find('[') {
+        let Some(mid) = rest[open..].find("](").map(|i| open + i) else {
+            break;
+        };
+        let Some(close) = rest[mid..].find(')').map(|i| mid + i) else {
+            break;
+        };
+
+        res.push_str(&rest[..open]);
+        res.push_str(&rest[open + 1..mid]);
+        rest = &rest[close + 1..];
+    }
+    res.push_str(rest);
+
+    res
+}
+
+/// Удаляет вступление вида `"Ответ:"` в начале текста.
+fn strip_preamble(text: &str) -> &str {
+    let Some((head, rest)) = text.split_once(':') else {
+        return text;
+    };
+
+    let head = head.trim().to_lowercase();
+    if PREAMBLES.contains(&head.as_str()) {
+        rest.trim_start()
+    } else {
+        text
+    }
+}
+
+/// Удаляет кавычки, обрамляющие текст целиком.
+fn strip_quotes(text: &str) -> &str {
+    for &(open, close) in QUOTES {
+        if let Some(inner) = text.strip_prefix(open).and_then(|v| v.strip_suffix(close))
+            && !inner.contains([open, close])
+        {
+            return inner;
+        }
+    }
+
+    text
+}
+
+/// Сокращает текст до `max_chars` символов: по концу последнего поместившегося
+/// предложения, а если его нет — по границе слова с многоточием.
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let cut = text
+        .char_indices()
+        .nth(max_chars)
+        .map_or(text.len(), |(i, _)| i);
+    let head = &text[..cut];
+
+    if let Some(end) = head.rfind(['.', '!', '?', '…'])
+        && end > 0
+    {
+        let end = end + head[end..].chars().next().map_or(1, char::len_utf8);
+        return head[..end].trim_end().to_string();
+    }
+
+    // Многоточие занимает один символ, поэтому слово обрезается до `max_chars - 1`.
+    let cut = head
+        .char_indices()
+        .nth(max_chars.saturating_sub(1))
+        .map_or(head.len(), |(i, _)| i);
+    let head = &head[..cut];
+    let head = head.rfind(char::is_whitespace).map_or(head, |i| &head[..i]);
+
+    format!("{}…", head.trim_end())
+}
+
+#[test]
+fn prepare_answer_test() {
+    let raw = "<think>\nПокупатель спрашивает о размере.\n</think>\n\n**Ответ:** «Здравствуйте! Да, размер M есть в наличии.»\n";
+    assert_eq!(
+        prepare_answer(raw, 5000).unwrap(),
+        "Здравствуйте! Да, размер M есть в наличии."
+    );
+
+    assert_eq!(
+        clean_answer(
+            "рассуждения</think>## Спасибо!\n\n\n* [Подробнее](https://example.com) в описании\n---"
+        ),
+        "Спасибо!\n\n- Подробнее в описании"
+    );
+    assert_eq!(clean_answer("Цена: 100 руб."), "Цена: 100 руб.");
+
+    assert_eq!(truncate("Первое. Второе предложение.", 20), "Первое.");
+    assert_eq!(truncate("Очень длинное слово", 10), "Очень…");
+
+    assert!(prepare_answer("<think>только рассуждения", 5000).is_err());
+    assert!(prepare_answer(" «» ", 5000).is_err());
+}
@@ -0,0 +1,376 @@
+use super::{AiProvider, ChatRequest, ChatResponse, ChatStreamEvent, Usage};
+use crate::{
+    config::{FallbackModels, LLmConfig, ModelPrice},
+    db::{self, AiCall},
+    error::{Error, Result},
+};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+/// Задача, для которой выбирается цепочка моделей.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Task {
+    /// AI-сводка о товаре.
+    Summary,
+    /// Ответ на вопрос.
+    Question,
+    /// Ответ на отзыв.
+    Review,
+}
+
+impl Task {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Task::Summary => "summary",
+            Task::Question => "question",
+            Task::Review => "review",
+        }
+    }
+
+    /// Критичные задачи выполняются и после превышения месячного бюджета.
+    #[inline]
+    pub fn is_critical(&self) -> bool {
+        matches!(self, Task::Question | Task::Review)
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "summary" => Some(Task::Summary),
+            "question" => Some(Task::Question),
+            "review" => Some(Task::Review),
+            _ => None,
+        }
+    }
+}
+
+/// Ответ модели вместе с моделью, которая его сформировала.
+#[derive(Debug)]
+pub struct RoutedResponse {
+    pub model: String,
+    pub response: ChatResponse,
+}
+
+/// Маршрутизатор запросов к моделям: перебирает основную модель запроса и резервные
+/// модели задачи, пока одна из них не ответит.
+///
+/// К следующей модели переходит при 429, 5xx, таймаутах, ошибках провайдера и
+/// пустом ответе; прочие ошибки (например, 401) возвращаются сразу. Модель, вернувшая
+/// такую ошибку, не используется в течение `cooldown`, если есть доступные модели.
+///
+/// Каждая попытка записывается в журнал `ai_call` с токенами, задержкой и стоимостью
+/// по ценам `prices`. Если задан `monthly_budget` и расходы с начала месяца его достигли,
+/// некритичные задачи (см. `Task::is_critical`) завершаются ошибкой `Error::Budget`.
+pub struct ModelRouter {
+    provider: AiProvider,
+    fallback: FallbackModels,
+    cooldown: Duration,
+    /// Момент окончания паузы модели.
+    cooldowns: Mutex<HashMap<String, Instant>>,
+    prices: HashMap<String, ModelPrice>,
+    monthly_budget: Option<f64>,
+}
+
+impl ModelRouter {
+    pub fn new(provider: AiProvider, fallback: FallbackModels, cooldown: Duration) -> Self {
+        Self {
+            provider,
+            fallback,
+            cooldown,
+            cooldowns: Default::default(),
+            prices: HashMap::new(),
+            monthly_budget: None,
+        }
+    }
+
+    pub fn from_config(cfg: &LLmConfig, http: reqwest::Client) -> Result<Self> {
+        Ok(Self {
+            prices: cfg.prices.clone(),
+            monthly_budget: cfg.monthly_budget,
+            ..Self::new(
+                AiProvider::from_config(cfg, http)?,
+                cfg.fallback.clone(),
+                cfg.cooldown(),
+            )
+        })
+    }
+
+    #[inline]
+    pub fn provider(&self) -> &AiProvider {
+        &self.provider
+    }
+
+    /// Цепочка моделей задачи `task` с основной моделью `model` в порядке перебора:
+    /// сначала доступные, затем находящиеся на паузе в порядке её окончания.
+    pub fn chain(&self, task: Task, model: &str) -> Vec<String> {
+        let fallback = match task {
+            Task::Summary => &self.fallback.summary,
+            Task::Question => &self.fallback.question,
+            Task::Review => &self.fallback.review,
+        };
+
+        let mut chain = Vec::<String>::with_capacity(fallback.len() + 1);
+        for m in std::iter::once(model).chain(fallback.iter().map(String::as_str)) {
+            if !m.is_empty() && !chain.iter().any(|v| v == m) {
+                chain.push(m.to_string());
+            }
+        }
+
+        let now = Instant::now();
+        let mut cooldowns = self.cooldowns.lock().unwrap();
+        cooldowns.retain(|_, until| *until > now);
+
+        // Стабильная сортировка сохраняет порядок доступных моделей.
+        chain.sort_by_key(|m| cooldowns.get(m).copied());
+
+        chain
+    }
+
+    /// Запрашивает ответ у моделей цепочки задачи `task` магазина `shop`, начиная с `r.model`.
+    pub async fn chat(&self, shop: &str, task: Task, r: &ChatRequest) -> Result<RoutedResponse> {
+        self.check_budget(task).await?;
+
+        let mut last_error = None;
+
+        for model in self.chain(task, &r.model) {
+            let req = ChatRequest {
+                model: model.clone(),
+                ..r.clone()
+            };
+
+            let started = Instant::now();
+            let res = self.provider.chat(&req).await;
+            let mut call = self.call(shop, task, &model, started);
+
+            let err = match res {
+                Ok(response) => {
+                    if let Some(ref usage) = response.usage {
+                        call.set_usage(usage, self.prices.get(&model));
+                    }
+
+                    if !is_empty_response(&response) {
+                        record(&call).await;
+                        return Ok(RoutedResponse { model, response });
+                    }
+                    Error::AiProvider("empty choices".into())
+                }
+                Err(e) => e,
+            };
+
+            call.error = Some(err.to_string());
+            record(&call).await;
+
+            if !is_fallback_error(&err) {
+                return Err(err);
+            }
+
+            self.pause(&model, &err);
+            last_error = Some(err);
+        }
+
+        Err(last_error.unwrap_or_else(|| Error::AiProvider("no models to route".into())))
+    }
+
+    /// Запрашивает ответ в потоковом режиме. К следующей модели переходит только при
+    /// ошибке до начала потока. Возвращает модель и канал событий.
+    pub async fn chat_stream(
+        &self,
+        shop: &str,
+        task: Task,
+        r: &ChatRequest,
+    ) -> Result<(String, UnboundedReceiver<Result<ChatStreamEvent>>)> {
+        self.check_budget(task).await?;
+
+        let mut last_error = None;
+
+        for model in self.chain(task, &r.model) {
+            let req = ChatRequest {
+                model: model.clone(),
+                ..r.clone()
+            };
+
+            let started = Instant::now();
+            let err = match self.provider.chat_stream(&req).await {
+                Ok(rx) => {
+                    let call = self.call(shop, task, &model, started);
+                    let price = self.prices.get(&model).copied();
+                    return Ok((model, record_stream(call, price, started, rx)));
+                }
+                Err(e) => e,
+            };
+
+            let mut call = self.call(shop, task, &model, started);
+            call.error = Some(err.to_string());
+            record(&call).await;
+
+            if !is_fallback_error(&err) {
+                return Err(err);
+            }
+
+            self.pause(&model, &err);
+            last_error = Some(err);
+        }
+
+        Err(last_error.unwrap_or_else(|| Error::AiProvider("no models to route".into())))
+    }
+
+    /// Запись журнала о попытке, начатой в `started`.
+    fn call(&self, shop: &str, task: Task, model: &str, started: Instant) -> AiCall {
+        AiCall {
+            shop: shop.to_string(),
+            task: task.as_str().to_string(),
+            model: model.to_string(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            ..Default::default()
+        }
+    }
+
+    /// Проверяет месячный бюджет перед выполнением некритичной задачи.
+    async fn check_budget(&self, task: Task) -> Result<()> {
+        let Some(budget) = self.monthly_budget.filter(|_| !task.is_critical()) else {
+            return Ok(());
+        };
+
+        let spent = db::select_ai_cost_since(month_start()).await?;
+        if spent >= budget {
+            return Err(Error::Budget(format!(
+                "monthly budget {budget} is exhausted ({spent:.4} spent), task {} is paused until next month",
+                task.as_str()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn pause(&self, model: &str, err: &Error) {
+        eprintln!(
+            "Модель {model} недоступна ({err}), пауза {} с.",
+            self.cooldown.as_secs()
+        );
+
+        self.cooldowns
+            .lock()
+            .unwrap()
+            .insert(model.to_string(), Instant::now() + self.cooldown);
+    }
+}
+
+impl AiCall {
+    fn set_usage(&mut self, usage: &Usage, price: Option<&ModelPrice>) {
+        self.prompt_tokens = usage.prompt_tokens;
+        self.completion_tokens = usage.completion_tokens;
+        self.cost = price.map_or(0.0, |p| {
+            p.cost(usage.prompt_tokens, usage.completion_tokens)
+        });
+    }
+}
+
+/// Записывает вызов в журнал. Ошибка записи не прерывает генерацию ответа.
+async fn record(call: &AiCall) {
+    if let Err(e) = db::insert_ai_call(call).await {
+        eprintln!("Ошибка записи вызова AI провайдера в журнал: {e}");
+    }
+}
+
+/// Пересылает события потока и после его завершения записывает вызов в журнал
+/// с токенами из события `Usage` и общим временем генерации.
+fn record_stream(
+    mut call: AiCall,
+    price: Option<ModelPrice>,
+    started: Instant,
+    mut rx: UnboundedReceiver<Result<ChatStreamEvent>>,
+) -> UnboundedReceiver<Result<ChatStreamEvent>> {
+    let (tx, out) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            match event {
+                Ok(ChatStreamEvent::Usage(ref usage)) => call.set_usage(usage, price.as_ref()),
+                Err(ref e) => call.error = Some(e.to_string()),
+                _ => {}
+            }
+
+            if tx.send(event).is_err() {
+                call.error
+                    .get_or_insert_with(|| "stream closed by receiver".into());
+                break;
+            }
+        }
+
+        call.latency_ms = started.elapsed().as_millis() as u64;
+        record(&call).await;
+    });
+
+    out
+}
+
+/// Начало текущего месяца (UTC), Unix timestamp.
+pub fn month_start() -> u64 {
+    let today = time::OffsetDateTime::now_utc().date();
+    let first = today.replace_day(1).unwrap_or(today);
+
+    first.midnight().assume_utc().unix_timestamp().max(0) as u64
+}
+
+/// Ответ без текста и без вызовов инструментов.
+fn is_empty_response(r: &ChatResponse) -> bool {
+    r.choices
+        .first()
+        .is_none_or(|c| c.message.content.trim().is_empty() && c.message.tool_calls.is_empty())
+}
+
+/// Ошибка, после которой запрос повторяется со следующей моделью цепочки:
+/// 408, 429, 5xx, таймауты и ошибки соединения, а также ошибки, о которых сообщил провайдер.
+#[inline]
+fn is_fallback_error(e: &Error) -> bool {
+    e.is_transient() || matches!(e, Error::AiProvider(_))
+}
+
+#[tokio::test]
+async fn model_router_test() {
+    use super::{Conversation, MockBackend};
+
+    let fallback = FallbackModels {
+        question: vec!["b".into(), "a".into(), "c".into()],
+        ..Default::default()
+    };
+    let router = ModelRouter::new(
+        AiProvider::Mock(MockBackend::new(Some("Ответ".into()))),
+        fallback,
+        Duration::from_secs(60),
+    );
+
+    assert_eq!(router.chain(Task::Question, "a"), ["a", "b", "c"]);
+    assert_eq!(router.chain(Task::Summary, "a"), ["a"]);
+
+    router.pause("a", &Error::AiProvider("empty choices".into()));
+    router.pause("b", &Error::AiProvider("empty choices".into()));
+    assert_eq!(router.chain(Task::Question, "a"), ["c", "a", "b"]);
+
+    let res = router
+        .chat(
+            "test",
+            Task::Question,
+            &Conversation::new().user("?").into_request("a"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.model, "c");
+
+    let router = ModelRouter::new(
+        AiProvider::Mock(MockBackend::new(Some(" ".into()))),
+        FallbackModels::default(),
+        Duration::from_secs(60),
+    );
+    let res = router
+        .chat(
+            "test",
+            Task::Review,
+            &Conversation::new().user("?").into_request("a"),
+        )
+        .await;
+    assert!(matches!(res, Err(Error::AiProvider(m)) if m == "empty choices"));
+}
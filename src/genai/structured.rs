@@ -0,0 +1,182 @@
+use super::{
+    ChatRequest, Message, ModelRouter, ResponseFormat, RoutedResponse, Task, strip_reasoning,
+};
+use crate::error::{Error, Result};
+use serde::de::DeserializeOwned;
+
+/// Количество повторных запросов с просьбой исправить некорректный JSON.
+const JSON_REPAIR_ATTEMPTS: u32 = 2;
+
+/// Тип, в который десериализуется структурированный ответ модели.
+pub trait StructuredOutput: DeserializeOwned {
+    /// Имя схемы ответа (`response_format.json_schema.name`).
+    const NAME: &'static str;
+
+    /// JSON схема ответа.
+    fn schema() -> serde_json::Value;
+
+    /// Формат ответа для `ChatRequest::response_format`.
+    fn response_format() -> ResponseFormat {
+        ResponseFormat::json_schema(Self::NAME, Self::schema())
+    }
+}
+
+/// Структурированный ответ вместе с моделью, которая его сформировала.
+#[derive(Debug)]
+pub struct Structured<T> {
+    pub value: T,
+    pub model: String,
+}
+
+impl ModelRouter {
+    /// Запрашивает ответ в формате JSON и десериализует его в `T`
+    /// (см. `ModelRouter::parse_json_response`).
+    /// Если `r.response_format` не задан, запрашивается JSON по схеме `T`.
+    pub async fn chat_json<T: StructuredOutput>(
+        &self,
+        shop: &str,
+        task: Task,
+        r: &ChatRequest,
+    ) -> Result<Structured<T>> {
+        let req = ChatRequest {
+            response_format: r
+                .response_format
+                .clone()
+                .or_else(|| Some(T::response_format())),
+            ..r.clone()
+        };
+
+        let routed = self.chat(shop, task, &req).await?;
+        self.parse_json_response(shop, task, req, routed).await
+    }
+
+    /// Десериализует в `T` ответ `routed` модели на запрос `req`.
+    ///
+    /// Если модель вернула некорректный JSON, запрос повторяется не более
+    /// `JSON_REPAIR_ATTEMPTS` раз: к диалогу добавляются ответ модели и просьба
+    /// исправить его с текстом ошибки разбора.
+    pub async fn parse_json_response<T: StructuredOutput>(
+        &self,
+        shop: &str,
+        task: Task,
+        mut req: ChatRequest,
+        mut routed: RoutedResponse,
+    ) -> Result<Structured<T>> {
+        let mut attempt = 0;
+
+        loop {
+            let content = routed
+                .response
+                .take_message(0)
+                .map(|m| m.content)
+                .unwrap_or_default();
+
+            let err = match parse_json::<T>(&content) {
+                Ok(value) => {
+                    return Ok(Structured {
+                        value,
+                        model: routed.model,
+                    });
+                }
+                Err(e) => e,
+            };
+
+            if attempt >= JSON_REPAIR_ATTEMPTS {
+                return Err(Error::InvalidAnswer(format!(
+                    "model {} returned invalid JSON: {err}",
+                    routed.model
+                )));
+            }
+            attempt += 1;
+
+            eprintln!(
+                "Модель {} вернула некорректный JSON ({err}), попытка исправления {attempt}.",
+                routed.model
+            );
+
+            // Исправление запрашивается у модели, ответившей с ошибкой, без инструментов.
+            req.model = routed.model;
+            req.tool_choice = req.tools.as_ref().map(|_| "none".into());
+            req.messages.push(Message::assistant(content));
+            req.messages.push(Message::user(format!(
+                "Ответ не является корректным JSON по схеме: {err}. Верни только исправленный JSON без пояснений и разметки."
+            )));
+
+            routed = self.chat(shop, task, &req).await?;
+        }
+    }
+}
+
+/// Десериализует JSON из ответа модели. Рассуждения `<think>`, блоки кода markdown
+/// и текст вокруг JSON объекта или массива отбрасываются.
+pub fn parse_json<T: DeserializeOwned>(text: &str) -> Result<T> {
+    let text = strip_reasoning(text);
+    let text = strip_code_fence(text.trim());
+
+    let err = match serde_json::from_str::<T>(text) {
+        Ok(v) => return Ok(v),
+        Err(e) => e,
+    };
+
+    // Пояснения до и после JSON.
+    let start = text.find(['{', '[']);
+    let end = text.rfind(['}', ']']);
+    if let (Some(start), Some(end)) = (start, end)
+        && (start > 0 || end + 1 < text.len())
+        && start < end
+    {
+        return Ok(serde_json::from_str::<T>(&text[start..=end])?);
+    }
+
+    Err(err.into())
+}
+
+/// Возвращает содержимое первого блока кода ```` ```json ... ``` ````, если он есть.
+fn strip_code_fence(text: &str) -> &str {
+    let Some(start) = text.find("```") else {
+        return text;
+    };
+
+    let rest = &text[start + 3..];
+    // Язык блока (`json`) до конца строки.
+    let rest = rest.split_once('\n').map_or(rest, |(_, body)| body);
+
+    match rest.find("```") {
+        Some(end) => rest[..end].trim(),
+        None => rest.trim(),
+    }
+}
+
+#[test]
+fn parse_json_test() {
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Reply {
+        answer: String,
+        escalate: bool,
+    }
+
+    let reply = Reply {
+        answer: "Да".into(),
+        escalate: false,
+    };
+
+    assert_eq!(
+        parse_json::<Reply>(r#"{"answer":"Да","escalate":false}"#).unwrap(),
+        reply
+    );
+    assert_eq!(
+        parse_json::<Reply>(
+            "<think>Нужен JSON.</think>\n```json\n{\"answer\": \"Да\", \"escalate\": false}\n```"
+        )
+        .unwrap(),
+        reply
+    );
+    assert_eq!(
+        parse_json::<Reply>("Вот ответ: {\"answer\": \"Да\", \"escalate\": false}. Готово!")
+            .unwrap(),
+        reply
+    );
+
+    assert!(parse_json::<Reply>(r#"{"answer":"Да"}"#).is_err());
+    assert!(parse_json::<Reply>("Да").is_err());
+}
@@ -1,12 +1,12 @@
 use super::models::{DEFAULT_AUTHOR_NAME, NewQuestion, NewReview};
 use crate::error::{Error, Result};
-use crate::sellerapi::abcmodels::{NewFeedback, Product, ProductFormatInfo};
+use crate::sellerapi::abcmodels::{
+    NewFeedback, Product, ProductFormatInfo, ProductPrice, ProductStock,
+};
 use crate::sellerapi::ozmodels::params::PRODUCT_LIST_MAX_LIMIT;
 use crate::sellerapi::{OzonSellerClient, WbSellerClient, ozmodels, wbmodels};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::Write;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::usize;
 use std::{sync::Arc, time::Duration};
 use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 
@@ -15,6 +15,13 @@ pub const WB_PLACE_FULL_SYMBOL: &str = "Wildberries";
 pub const OZON_PLACE_SYMBOL: &str = "oz";
 pub const WB_PLACE_SYMBOL: &str = "wb";
 
+/// Размер страницы, запрашиваемой наблюдателем. За один опрос наблюдатель
+/// запрашивает все страницы новых записей.
+const OBSERVER_BATCH_LIMIT: u32 = 100;
+
+/// Размер страницы при выгрузке обратной связи, ожидающей ответа (Wildberries).
+const UNANSWERED_PAGE_LIMIT: usize = 1000;
+
 /// Клиент для взаимодействия с конкретным маркетплейсом (Ozon или Wildberries).
 /// Хранит подключение к соответствующему клиенту в Arc.
 #[derive(Clone)]
@@ -40,6 +47,15 @@ impl SellerClient {
         }
     }
 
+    /// Максимальная длина ответа на вопрос или отзыв, символов.
+    #[inline]
+    pub fn answer_max_len(&self) -> usize {
+        match self {
+            Self::Ozon(_) => ozmodels::params::ANSWER_MAX_LEN,
+            Self::Wb(_) => wbmodels::params::ANSWER_MAX_LEN,
+        }
+    }
+
     pub async fn get_product_format_info(&self, product_id: &str) -> Result<ProductFormatInfo> {
         match self {
             Self::Ozon(cli) => {
@@ -198,19 +214,20 @@ impl SellerClient {
                         attrs.insert(c.name, val);
                     });
 
-                if let Some(first_photo) = card.photos.get(0) {
-                    if let Some((bucket_path, _)) = first_photo.big.split_once("/images/") {
-                        let rich_content = WbSellerClient::get_product_rich_content(bucket_path, 1)
-                            .await
-                            .ok()
-                            .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
-                            .and_then(|mut v| {
-                                sanitize_wb_rich_content_json(&mut v);
-                                serde_json::to_string(&v).ok()
-                            })
-                            .unwrap_or_else(|| "Empty".to_string());
-                        attrs.insert("Rich-контент JSON".to_owned(), rich_content);
-                    }
+                if let Some(first_photo) = card.photos.first()
+                    && let Some((bucket_path, _)) = first_photo.big.split_once("/images/")
+                {
+                    let rich_content = cli
+                        .get_product_rich_content(bucket_path, 1)
+                        .await
+                        .ok()
+                        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+                        .and_then(|mut v| {
+                            sanitize_wb_rich_content_json(&mut v);
+                            serde_json::to_string(&v).ok()
+                        })
+                        .unwrap_or_else(|| "Empty".to_string());
+                    attrs.insert("Rich-контент JSON".to_owned(), rich_content);
                 }
 
                 let (weight, r#box) = card
@@ -257,6 +274,102 @@ impl SellerClient {
         }
     }
 
+    /// Цена товара `product_id`.
+    pub async fn get_product_price(&self, product_id: &str) -> Result<ProductPrice> {
+        match self {
+            Self::Ozon(cli) => {
+                let product = Self::get_ozon_product(cli, product_id).await?;
+
+                Ok(ProductPrice {
+                    id: product_id.to_string(),
+                    price: product.marketing_price,
+                    old_price: product.old_price,
+                    currency: product.currency_code,
+                })
+            }
+            Self::Wb(cli) => {
+                let nm_id = product_id
+                    .parse()
+                    .map_err(|_| Error::ProductCtxData(format!("invalid nmid {product_id}")))?;
+
+                let goods = cli
+                    .get_products_price(1, None, Some(nm_id))
+                    .await?
+                    .data
+                    .list_goods
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| {
+                        Error::ProductCtxData(format!("not found price by nmid {product_id}"))
+                    })?;
+                let size = goods.sizes.into_iter().next().ok_or_else(|| {
+                    Error::ProductCtxData(format!("not found price by nmid {product_id}"))
+                })?;
+
+                Ok(ProductPrice {
+                    id: product_id.to_string(),
+                    price: size.discounted_price.to_string(),
+                    old_price: size.price.to_string(),
+                    currency: goods.currency_iso_code4217,
+                })
+            }
+        }
+    }
+
+    /// Остатки товара `product_id`, доступные для продажи.
+    pub async fn get_product_stock(&self, product_id: &str) -> Result<ProductStock> {
+        let mut stock = ProductStock {
+            id: product_id.to_string(),
+            available: 0,
+            warehouses: BTreeMap::new(),
+        };
+
+        match self {
+            Self::Ozon(cli) => {
+                let product = Self::get_ozon_product(cli, product_id).await?;
+
+                for item in product.stocks.stocks {
+                    let available = (item.present - item.reserved).max(0);
+                    *stock.warehouses.entry(item.source).or_default() += available;
+                    stock.available += available;
+                }
+            }
+            Self::Wb(cli) => {
+                let nm_id = product_id
+                    .parse::<i64>()
+                    .map_err(|_| Error::ProductCtxData(format!("invalid nmid {product_id}")))?;
+
+                // Самая ранняя дата возвращает все остатки.
+                for item in cli.get_supplier_stocks("2019-06-20").await? {
+                    if item.nm_id == nm_id {
+                        *stock.warehouses.entry(item.warehouse_name).or_default() += item.quantity;
+                        stock.available += item.quantity;
+                    }
+                }
+            }
+        }
+
+        Ok(stock)
+    }
+
+    async fn get_ozon_product(
+        cli: &OzonSellerClient,
+        product_id: &str,
+    ) -> Result<ozmodels::ProductInfo> {
+        let tmp = [product_id];
+        let filter = ozmodels::params::Filter {
+            sku: Some(&tmp[..]),
+            ..Default::default()
+        };
+
+        cli.get_product_info_list(&filter)
+            .await?
+            .items
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::ProductCtxData(format!("not found product by sku {product_id}")))
+    }
+
     /// Возвращает канал с товарами.
     pub fn all_products_stream(&self) -> UnboundedReceiver<Result<Product>> {
         let seller = self.clone();
@@ -335,7 +448,7 @@ impl SellerClient {
                     }
                 }
                 Self::Wb(cli) => {
-                    let mut limit = 100;
+                    let limit = 100;
 
                     let mut cursor = wbmodels::params::CardListCursor {
                         limit: Some(limit),
@@ -361,10 +474,7 @@ impl SellerClient {
 
                         let mut res = unsafe { res.unwrap_unchecked() };
 
-                        for (n, i) in res.cards.into_iter().enumerate() {
-                            if n as u32 == limit - 1 {
-                                break;
-                            }
+                        for i in res.cards {
                             let product = Product {
                                 id: i.nm_id.to_string(),
                                 name: i.title,
@@ -444,174 +554,400 @@ impl SellerClient {
     //     }
     // }
 
-    /// Вспомогательная функция: сортирует по `published_at` (по убыванию),
-    /// фильтрует записи старше `date_from` и возвращает до `limit` элементов.
-    fn process_questions(
-        mut qs: Vec<NewQuestion>,
-        limit: usize,
-        date_from: u64,
-    ) -> Vec<NewQuestion> {
-        qs.sort_by(|a, b| b.published_at.cmp(&a.published_at));
-        qs.into_iter()
-            .filter(|q| q.published_at >= date_from)
-            .take(limit)
-            .collect()
+    /// Вспомогательная функция: сортирует по `published_at` (по возрастанию),
+    /// фильтрует записи старше `date_from` и повторы с разных страниц.
+    fn process_questions(mut qs: Vec<NewQuestion>, date_from: u64) -> Vec<NewQuestion> {
+        let mut seen = HashSet::new();
+        qs.retain(|q| q.published_at >= date_from && seen.insert(q.id.clone()));
+        qs.sort_by_key(|q| q.published_at);
+        qs
     }
 
     /// Вспомогательная функция аналогично для отзывов.
-    fn process_reviews(mut rs: Vec<NewReview>, limit: usize, date_from: u64) -> Vec<NewReview> {
-        rs.sort_by(|a, b| b.published_at.cmp(&a.published_at));
-        rs.into_iter()
-            .filter(|r| r.published_at >= date_from)
-            .take(limit)
-            .collect()
+    fn process_reviews(mut rs: Vec<NewReview>, date_from: u64) -> Vec<NewReview> {
+        let mut seen = HashSet::new();
+        rs.retain(|r| r.published_at >= date_from && seen.insert(r.id.clone()));
+        rs.sort_by_key(|r| r.published_at);
+        rs
     }
 
-    /// Получить последние новые вопросы (с учётом `limit` и `date_from`).
+    /// Получить все новые вопросы, опубликованные не раньше `date_from`, в порядке публикации.
+    /// Страницы (по `limit` записей у Wildberries) запрашиваются, пока выдача не закончится,
+    /// поэтому при ошибке на любой странице не возвращается ничего.
     pub async fn get_last_new_questions(
         &self,
         limit: u32,
         date_from: u64,
     ) -> Result<Vec<NewQuestion>> {
+        let mut questions = Vec::new();
+
         match self {
-            Self::Ozon(cli) => cli
-                .get_question_list(
-                    Some(&ozmodels::params::QuestionListFilter {
-                        status: ozmodels::QuestionStatus::Unprocessed,
-                        date_from: Some(&unix_timestamp_to_rfc3339_format(date_from)),
-                        ..Default::default()
-                    }),
-                    None,
-                )
-                .await
-                .map(|res| {
-                    let mapped = res
-                        .questions
-                        .into_iter()
-                        .map(|q| NewQuestion {
-                            id: q.id,
-                            product_id: q.sku.to_string(),
-                            author_name: q.author_name,
-                            text: q.text,
-                            published_at: format_rfc3339_to_unix_timestamp(&q.published_at),
-                        })
-                        .collect::<Vec<_>>();
+            Self::Ozon(cli) => {
+                let date_from = unix_timestamp_to_rfc3339_format(date_from);
+                let filter = ozmodels::params::QuestionListFilter {
+                    status: ozmodels::QuestionStatus::Unprocessed,
+                    date_from: Some(&date_from),
+                    ..Default::default()
+                };
+                let mut last_id: Option<String> = None;
+
+                loop {
+                    let res = cli
+                        .get_question_list(Some(&filter), last_id.as_deref())
+                        .await?;
+
+                    if res.questions.is_empty() {
+                        break;
+                    }
+                    questions.extend(res.questions.into_iter().map(new_question_from_ozon));
 
-                    Self::process_questions(mapped, limit as usize, date_from)
-                }),
+                    if res.last_id.is_empty() || last_id.as_ref() == Some(&res.last_id) {
+                        break;
+                    }
+                    last_id = Some(res.last_id);
+                }
+            }
             Self::Wb(cli) => {
-                let take = limit
-                    .max(1)
-                    .min(wbmodels::params::QUESTION_MAX_LIMIT as u32);
-
-                cli.get_question_list(&wbmodels::params::QuestionsAndReviewsFilter {
-                    is_answered: false,
-                    take,
-                    skip: 0,
-                    date_from: Some(date_from),
-                    ..Default::default()
-                })
-                .await
-                .map(|res| {
-                    let mapped = res
-                        .questions
-                        .into_iter()
-                        .map(|q| NewQuestion {
-                            id: q.id,
-                            product_id: q.product_details.nm_id.to_string(),
-                            author_name: DEFAULT_AUTHOR_NAME.to_string(),
-                            text: q.text,
-                            published_at: format_rfc3339_to_unix_timestamp(&q.created_date),
+                let take = limit.clamp(1, wbmodels::params::QUESTION_MAX_LIMIT as u32);
+                let mut skip = 0;
+
+                loop {
+                    let res = cli
+                        .get_question_list(&wbmodels::params::QuestionsAndReviewsFilter {
+                            is_answered: false,
+                            take,
+                            skip,
+                            order: Some(wbmodels::params::ORDER_DATE_ASC),
+                            date_from: Some(date_from),
+                            ..Default::default()
                         })
-                        .collect::<Vec<_>>();
+                        .await?;
+                    let len = res.questions.len();
 
-                    Self::process_questions(mapped, limit as usize, date_from)
-                })
+                    questions.extend(res.questions.into_iter().map(new_question_from_wb));
+
+                    skip += len as u32;
+                    if len < take as usize || skip as usize >= wbmodels::params::QUESTION_MAX_SKIP {
+                        break;
+                    }
+                }
             }
         }
+
+        Ok(Self::process_questions(questions, date_from))
     }
 
-    /// Получить последние новые отзывы (с учётом `limit` и `date_from`).
+    /// Получить все новые отзывы, опубликованные не раньше `date_from`, в порядке публикации.
+    /// Ozon не фильтрует отзывы по дате, поэтому страницы запрашиваются от новых к старым,
+    /// пока не встретится отзыв старше `date_from`.
     pub async fn get_last_new_reviews(&self, limit: u32, date_from: u64) -> Result<Vec<NewReview>> {
+        let mut reviews = Vec::new();
+
         match self {
             Self::Ozon(cli) => {
-                let take = limit
-                    .max(ozmodels::params::REVIEW_MIN_LIMIT as u32)
-                    .min(ozmodels::params::REVIEW_MAX_LIMIT as u32);
-
-                cli.get_review_list(
-                    take,
-                    Some(&ozmodels::params::ReviewStatus::Unprocessed),
-                    None,
-                    None,
-                )
-                .await
-                .map(|res| {
-                    let mapped = res
+                let limit = limit.clamp(
+                    ozmodels::params::REVIEW_MIN_LIMIT as u32,
+                    ozmodels::params::REVIEW_MAX_LIMIT as u32,
+                );
+                let mut last_id: Option<String> = None;
+
+                loop {
+                    let res = cli
+                        .get_review_list(
+                            limit,
+                            Some(&ozmodels::params::ReviewStatus::Unprocessed),
+                            Some(&ozmodels::params::SortDir::Desc),
+                            last_id.as_deref(),
+                        )
+                        .await?;
+                    let page = res
                         .reviews
                         .into_iter()
-                        .map(|r| NewReview {
-                            id: r.id,
-                            product_id: r.sku.to_string(),
-                            author_name: DEFAULT_AUTHOR_NAME.to_string(),
-                            text: r.text,
-                            score: r.rating as f32,
-                            photos_amount: r.photos_amount as u16,
-                            videos_amount: r.videos_amount as u16,
-                            published_at: format_rfc3339_to_unix_timestamp(&r.published_at),
-                        })
+                        .map(new_review_from_ozon)
                         .collect::<Vec<_>>();
+                    let reached = page.last().is_none_or(|r| r.published_at < date_from);
 
-                    Self::process_reviews(mapped, limit as usize, date_from)
-                })
+                    reviews.extend(page);
+
+                    if reached || !res.has_next || res.last_id.is_empty() {
+                        break;
+                    }
+                    last_id = Some(res.last_id);
+                }
             }
             Self::Wb(cli) => {
-                let take = limit.max(1).min(wbmodels::params::REVIEW_MAX_LIMIT as u32);
+                let take = limit.clamp(1, wbmodels::params::REVIEW_MAX_LIMIT as u32);
+                let mut skip = 0;
+
+                loop {
+                    let res = cli
+                        .get_review_list(&wbmodels::params::QuestionsAndReviewsFilter {
+                            is_answered: false,
+                            take,
+                            skip,
+                            order: Some(wbmodels::params::ORDER_DATE_ASC),
+                            date_from: Some(date_from),
+                            ..Default::default()
+                        })
+                        .await?;
+                    let len = res.reviews.len();
 
-                cli.get_review_list(&wbmodels::params::QuestionsAndReviewsFilter {
-                    is_answered: false,
-                    take,
-                    skip: 0,
-                    date_from: Some(date_from),
-                    ..Default::default()
-                })
-                .await
-                .map(|res| {
-                    let mapped = res
-                        .reviews
-                        .into_iter()
-                        .map(|r| {
-                            let mut text = "".to_string();
+                    reviews.extend(res.reviews.into_iter().map(new_review_from_wb));
 
-                            if !r.pros.is_empty() {
-                                let _ = write!(&mut text, "Достоинства: {}\n", r.pros);
-                            }
-                            if !r.cons.is_empty() {
-                                let _ = write!(&mut text, "Недостатки: {}\n", r.cons);
+                    skip += len as u32;
+                    if len < take as usize || skip as usize >= wbmodels::params::REVIEW_MAX_SKIP {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(Self::process_reviews(reviews, date_from))
+    }
+
+    /// Запускает поток всех вопросов и отзывов, ожидающих ответа, с учётом фильтра `filter`.
+    /// Страницы запрашиваются постранично (`skip`/`take` у Wildberries, `last_id` у Ozon)
+    /// независимо от скорости обработки получателем: сначала вопросы, затем отзывы.
+    /// У Wildberries записи отправляются после выгрузки всех страниц, так как ответы
+    /// получателя сдвигают выдачу по `skip`.
+    /// Запрос страницы повторяется после временных ошибок по `retry`. Ошибка страницы
+    /// передаётся в канал, после чего выгрузка продолжается со следующей страницы
+    /// (Wildberries) или со следующего типа обратной связи (Ozon).
+    pub fn unanswered_feedback_stream(
+        &self,
+        filter: UnansweredFilter,
+        retry: RetryPolicy,
+    ) -> UnboundedReceiver<Result<NewFeedback>> {
+        let seller = self.clone();
+
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let res = match &seller {
+                Self::Ozon(cli) => Self::send_unanswered_ozon(cli, &filter, &retry, &tx).await,
+                Self::Wb(cli) => Self::send_unanswered_wb(cli, &filter, &retry, &tx).await,
+            };
+
+            if let Err(e) = res {
+                let _ = tx.send(Err(e));
+            }
+        });
+
+        rx
+    }
+
+    async fn send_unanswered_ozon(
+        cli: &OzonSellerClient,
+        filter: &UnansweredFilter,
+        retry: &RetryPolicy,
+        tx: &UnboundedSender<Result<NewFeedback>>,
+    ) -> Result<()> {
+        if filter.includes("question") {
+            let date_from = filter.date_from.map(unix_timestamp_to_rfc3339_format);
+            let date_to = filter.date_to.map(unix_timestamp_to_rfc3339_format);
+            let mut last_id: Option<String> = None;
+
+            loop {
+                let filter_params = ozmodels::params::QuestionListFilter {
+                    status: ozmodels::QuestionStatus::Unprocessed,
+                    date_from: date_from.as_deref(),
+                    date_to: date_to.as_deref(),
+                };
+                let res = match retry
+                    .retry(|| cli.get_question_list(Some(&filter_params), last_id.as_deref()))
+                    .await
+                {
+                    Ok(res) => res,
+                    // Следующую страницу без `last_id` не запросить: выгрузка вопросов
+                    // прерывается, отзывы выгружаются дальше.
+                    Err(e) => {
+                        if tx.send(Err(e)).is_err() {
+                            return Ok(());
+                        }
+                        break;
+                    }
+                };
+
+                if res.questions.is_empty() {
+                    break;
+                }
+
+                for q in res.questions.into_iter().map(new_question_from_ozon) {
+                    if filter.matches(&q.product_id, q.published_at)
+                        && tx.send(Ok(NewFeedback::Question(q))).is_err()
+                    {
+                        return Ok(());
+                    }
+                }
+
+                if res.last_id.is_empty() || last_id.as_ref() == Some(&res.last_id) {
+                    break;
+                }
+                last_id = Some(res.last_id);
+            }
+        }
+
+        if filter.includes("review") {
+            let mut last_id: Option<String> = None;
+
+            loop {
+                let res = retry
+                    .retry(|| {
+                        cli.get_review_list(
+                            ozmodels::params::REVIEW_MAX_LIMIT as u32,
+                            Some(&ozmodels::params::ReviewStatus::Unprocessed),
+                            Some(&ozmodels::params::SortDir::Asc),
+                            last_id.as_deref(),
+                        )
+                    })
+                    .await;
+                let res = match res {
+                    Ok(res) => res,
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                        break;
+                    }
+                };
+
+                for r in res.reviews.into_iter().map(new_review_from_ozon) {
+                    if filter.matches(&r.product_id, r.published_at)
+                        && tx.send(Ok(NewFeedback::Review(r))).is_err()
+                    {
+                        return Ok(());
+                    }
+                }
+
+                if !res.has_next || res.last_id.is_empty() {
+                    break;
+                }
+                last_id = Some(res.last_id);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send_unanswered_wb(
+        cli: &WbSellerClient,
+        filter: &UnansweredFilter,
+        retry: &RetryPolicy,
+        tx: &UnboundedSender<Result<NewFeedback>>,
+    ) -> Result<()> {
+        // Wildberries фильтрует по одному nmID, поэтому для списка товаров выполняется
+        // отдельная выгрузка по каждому из них.
+        let nm_ids = if filter.product_ids.is_empty() {
+            vec![None]
+        } else {
+            filter
+                .product_ids
+                .iter()
+                .map(|id| {
+                    id.parse::<i64>().map(Some).map_err(|_| {
+                        Error::ProductCtxData(format!("invalid Wildberries nmID \"{id}\""))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        let page = |nm_id: Option<i64>, take: usize, skip: u32| {
+            wbmodels::params::QuestionsAndReviewsFilter {
+                is_answered: false,
+                nm_id,
+                take: take as u32,
+                skip,
+                order: Some(wbmodels::params::ORDER_DATE_ASC),
+                date_from: filter.date_from,
+                date_to: filter.date_to,
+            }
+        };
+
+        // Ответ на отправленную запись убирает её из выдачи неотвеченных и сдвигает
+        // следующие страницы, поэтому все страницы товара выгружаются до отправки записей.
+        let mut seen = HashSet::new();
+
+        if filter.includes("question") {
+            for &nm_id in &nm_ids {
+                let mut questions = Vec::new();
+                let mut skip = 0;
+
+                loop {
+                    let take = UNANSWERED_PAGE_LIMIT.min(wbmodels::params::QUESTION_MAX_LIMIT);
+                    let params = page(nm_id, take, skip);
+                    let res = match retry.retry(|| cli.get_question_list(&params)).await {
+                        Ok(res) => res,
+                        // Страница пропускается: записи на ней будут получены
+                        // при следующей выгрузке.
+                        Err(e) => {
+                            if tx.send(Err(e)).is_err() {
+                                return Ok(());
                             }
-                            if !r.text.is_empty() {
-                                let _ = write!(&mut text, "Комментарий: {}\n", r.text);
+                            skip += take as u32;
+                            if skip as usize >= wbmodels::params::QUESTION_MAX_SKIP {
+                                break;
                             }
+                            continue;
+                        }
+                    };
+                    let len = res.questions.len();
+
+                    questions.extend(res.questions.into_iter().map(new_question_from_wb));
+
+                    skip += len as u32;
+                    if len < take || skip as usize >= wbmodels::params::QUESTION_MAX_SKIP {
+                        break;
+                    }
+                }
+
+                for q in questions {
+                    if seen.insert(q.id.clone()) && tx.send(Ok(NewFeedback::Question(q))).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
 
-                            let text = text.trim().to_string();
-
-                            NewReview {
-                                id: r.id,
-                                product_id: r.product_details.nm_id.to_string(),
-                                author_name: r.user_name,
-                                text: text,
-                                score: r.product_valuation as f32,
-                                photos_amount: r.photo_links.map(|v| v.len()).unwrap_or(0) as u16,
-                                videos_amount: r.video.map(|_| 1).unwrap_or(0) as u16,
-                                published_at: format_rfc3339_to_unix_timestamp(&r.created_date),
+        if filter.includes("review") {
+            for &nm_id in &nm_ids {
+                let mut reviews = Vec::new();
+                let mut skip = 0;
+
+                loop {
+                    let take = UNANSWERED_PAGE_LIMIT.min(wbmodels::params::REVIEW_MAX_LIMIT);
+                    let params = page(nm_id, take, skip);
+                    let res = match retry.retry(|| cli.get_review_list(&params)).await {
+                        Ok(res) => res,
+                        // Страница пропускается: записи на ней будут получены
+                        // при следующей выгрузке.
+                        Err(e) => {
+                            if tx.send(Err(e)).is_err() {
+                                return Ok(());
                             }
-                        })
-                        .collect::<Vec<_>>();
+                            skip += take as u32;
+                            if skip as usize >= wbmodels::params::REVIEW_MAX_SKIP {
+                                break;
+                            }
+                            continue;
+                        }
+                    };
+                    let len = res.reviews.len();
 
-                    Self::process_reviews(mapped, limit as usize, date_from)
-                })
+                    reviews.extend(res.reviews.into_iter().map(new_review_from_wb));
+
+                    skip += len as u32;
+                    if len < take || skip as usize >= wbmodels::params::REVIEW_MAX_SKIP {
+                        break;
+                    }
+                }
+
+                for r in reviews {
+                    if seen.insert(r.id.clone()) && tx.send(Ok(NewFeedback::Review(r))).is_err() {
+                        return Ok(());
+                    }
+                }
             }
         }
+
+        Ok(())
     }
 
     // /// Запускает наблюдатель, который шлёт разницу количества вопросов в канал.
@@ -692,269 +1028,151 @@ impl SellerClient {
     //     });
     // }
 
-    #[inline]
-    /// Вспомогательная функция: берёт первые 8 байт id (если есть) и конвертирует в u64,
-    /// возвращает кортеж (unique_id, published_at).
-    fn compute_unique_key(id: &str, timestamp: u64) -> (u64, u64) {
-        let uid = id
-            .as_bytes()
-            .get(..8)
-            .and_then(|b| b.try_into().ok())
-            .map(|arr: [u8; 8]| u64::from_be_bytes(arr))
-            .unwrap_or_default();
-        (uid, timestamp)
-    }
-
     /// Запускает наблюдатель, который при появлении новых вопросов шлёт `NewQuestion` в канал.
+    /// Вопросы отправляются в порядке публикации, начиная с позиции `cursor`.
+    /// Ошибки запросов передаются в канал, после чего опрос продолжается с паузой по `retry`.
+    /// Наблюдатель останавливается, только когда получатель закрыт.
     pub fn spawn_new_question_observer(
         &self,
         interval: Duration,
+        mut cursor: ObserverCursor,
+        retry: RetryPolicy,
     ) -> UnboundedReceiver<Result<NewQuestion>> {
         let seller = self.clone();
 
         let (tx, rx) = mpsc::unbounded_channel();
 
         tokio::spawn(async move {
-            let mut unique_set = CircularArr::<_, 16>::new();
-            let mut date_from = 0u64;
-            let mut once = true;
+            let mut failures = 0;
 
             loop {
-                match seller.get_last_new_questions(20, date_from).await {
+                let delay = match seller
+                    .get_last_new_questions(OBSERVER_BATCH_LIMIT, cursor.last_seen)
+                    .await
+                {
                     Ok(list) => {
-                        if once {
-                            date_from = list.first().map_or(0, |q| q.published_at + 1);
-                            once = !once;
-                            continue;
-                        }
+                        failures = 0;
 
                         for new_question in list {
-                            let unique = Self::compute_unique_key(
-                                &new_question.id,
-                                new_question.published_at,
-                            );
-
-                            if unique_set.as_slise().contains(&unique) {
+                            if !cursor.accept(&new_question.id, new_question.published_at) {
                                 continue;
                             }
 
-                            date_from = new_question.published_at - 1;
                             if tx.send(Ok(new_question)).is_err() {
                                 return;
                             }
-
-                            unique_set.add(unique);
                         }
+
+                        interval
                     }
                     Err(e) => {
-                        let _ = tx.send(Err(e));
-                        return;
+                        let delay = retry.delay(failures, &e);
+                        failures = failures.saturating_add(1);
+
+                        if tx.send(Err(e)).is_err() {
+                            return;
+                        }
+
+                        delay
                     }
-                }
-                tokio::time::sleep(interval).await;
+                };
+                tokio::time::sleep(delay).await;
             }
         });
 
         rx
-
-        //     while let Some(res) = rx.recv().await {
-        //         if let Err(e) = res {
-        //             let _ = chan.send(Err(e));
-        //             return;
-        //         }
-        //         let diff = res.unwrap().max(1);
-        //         match seller
-        //             .get_last_new_questions(
-        //                 diff,
-        //                 (time::UtcDateTime::now().unix_timestamp() as u64) - interval.as_secs() * 2
-        //                     + 1,
-        //             )
-        //             .await
-        //         {
-        //             Ok(list) => {
-        //                 dbg!(&list);
-        //                 for new_question in list {
-        //                     dbg!(&new_question);
-        //                     let unique = Self::compute_unique_key(
-        //                         &new_question.id,
-        //                         new_question.published_at,
-        //                     );
-        //                     dbg!(unique);
-        //                     if unique_set.as_slise().contains(&unique) {
-        //                         continue;
-        //                     }
-        //                     dbg!("try send");
-        //                     if chan.send(Ok(new_question)).is_err() {
-        //                         return;
-        //                     }
-        //                     unique_set.add(unique);
-        //                 }
-        //             }
-        //             Err(e) => {
-        //                 let _ = chan.send(Err(e));
-        //                 return;
-        //             }
-        //         }
-        //     }
-        // });
     }
 
     /// Запускает наблюдатель, который при появлении новых отзывов шлёт `NewReview` в канал.
+    /// Отзывы отправляются в порядке публикации, начиная с позиции `cursor`.
+    /// Ошибки обрабатываются так же, как в `spawn_new_question_observer`.
     pub fn spawn_new_review_observer(
         &self,
         interval: Duration,
+        mut cursor: ObserverCursor,
+        retry: RetryPolicy,
     ) -> UnboundedReceiver<Result<NewReview>> {
         let seller = self.clone();
 
         let (tx, rx) = mpsc::unbounded_channel();
 
         tokio::spawn(async move {
-            let mut unique_set = CircularArr::<_, 16>::new();
-            let mut date_from = 0u64;
-            let mut once = true;
+            let mut failures = 0;
 
             loop {
-                match seller.get_last_new_reviews(20, date_from).await {
+                let delay = match seller
+                    .get_last_new_reviews(OBSERVER_BATCH_LIMIT, cursor.last_seen)
+                    .await
+                {
                     Ok(list) => {
-                        if once {
-                            once = !once;
-                            date_from = list.first().map_or(0, |q| q.published_at + 1);
-                            continue;
-                        }
-
-                        for new_reviews in list {
-                            let unique =
-                                Self::compute_unique_key(&new_reviews.id, new_reviews.published_at);
+                        failures = 0;
 
-                            if unique_set.as_slise().contains(&unique) {
+                        for new_review in list {
+                            if !cursor.accept(&new_review.id, new_review.published_at) {
                                 continue;
                             }
 
-                            date_from = new_reviews.published_at - 1;
-                            if tx.send(Ok(new_reviews)).is_err() {
+                            if tx.send(Ok(new_review)).is_err() {
                                 return;
                             }
-
-                            unique_set.add(unique);
                         }
+
+                        interval
                     }
                     Err(e) => {
-                        let _ = tx.send(Err(e));
-                        return;
+                        let delay = retry.delay(failures, &e);
+                        failures = failures.saturating_add(1);
+
+                        if tx.send(Err(e)).is_err() {
+                            return;
+                        }
+
+                        delay
                     }
-                }
-                tokio::time::sleep(interval).await;
+                };
+                tokio::time::sleep(delay).await;
             }
         });
 
         rx
-
-        // tokio::spawn(async move {
-        //     let (tx, mut rx) = mpsc::unbounded_channel::<Result<_>>();
-        //     seller.spawn_has_new_review_observer(tx, interval);
-        //     let mut unique_set = CircularArr::<_, 16>::new();
-        //     while let Some(res) = rx.recv().await {
-        //         if let Err(e) = res {
-        //             let _ = chan.send(Err(e));
-        //             return;
-        //         }
-        //         let diff = res.unwrap().max(1);
-        //         let seller_0 = seller.clone();
-        //         match seller
-        //             .get_last_new_reviews(
-        //                 diff,
-        //                 (time::UtcDateTime::now().unix_timestamp() as u64) - 3600,
-        //             )
-        //             .await
-        //         {
-        //             Ok(list) => {
-        //                 for new_review in list {
-        //                     let unique =
-        //                         Self::compute_unique_key(&new_review.id, new_review.published_at);
-        //                     if unique_set.as_slise().contains(&unique) {
-        //                         continue;
-        //                     }
-        //                     if chan.send(Ok(new_review)).is_err() {
-        //                         return;
-        //                     }
-        //                     unique_set.add(unique);
-        //                 }
-        //             }
-        //             Err(e) => {
-        //                 let _ = chan.send(Err(e));
-        //                 return;
-        //             }
-        //         }
-        //     }
-        // });
     }
 
     /// Запускает наблюдатель, который при появлении новых вопросов или отзывов
-    /// шлёт `NewFeedback` в канал. Если один поток завершился, другой тоже остановится.
+    /// шлёт `NewFeedback` в канал. Ошибки каждого из наблюдателей передаются в канал
+    /// и не останавливают другой.
     pub fn spawn_new_feedback_observer(
         &self,
         question_interval: Duration,
         review_interval: Duration,
+        question_cursor: ObserverCursor,
+        review_cursor: ObserverCursor,
+        retry: RetryPolicy,
     ) -> UnboundedReceiver<Result<NewFeedback>> {
         let (tx, rx) = mpsc::unbounded_channel();
 
-        let stop_flag = Arc::new(AtomicBool::new(false));
-
         {
-            let seller = self.clone();
             let tx = tx.clone();
-            let stop_flag = stop_flag.clone();
-
-            let mut rx = seller.spawn_new_question_observer(question_interval);
+            let mut rx =
+                self.spawn_new_question_observer(question_interval, question_cursor, retry);
 
             tokio::spawn(async move {
-                while !stop_flag.load(Ordering::Relaxed) {
-                    if let Some(res) = rx.recv().await {
-                        match res {
-                            Ok(q) => {
-                                if tx.send(Ok(NewFeedback::Question(q))).is_err() {
-                                    break;
-                                }
-                            }
-                            Err(e) => {
-                                let _ = tx.send(Err(e));
-                                break;
-                            }
-                        }
-                    } else {
+                while let Some(res) = rx.recv().await {
+                    if tx.send(res.map(NewFeedback::Question)).is_err() {
                         break;
                     }
                 }
-                stop_flag.store(true, Ordering::Relaxed);
             });
         }
 
         {
-            let seller = self.clone();
-            let tx = tx.clone();
-            let stop_flag = stop_flag.clone();
-
-            let mut rx = seller.spawn_new_review_observer(review_interval);
+            let mut rx = self.spawn_new_review_observer(review_interval, review_cursor, retry);
 
             tokio::spawn(async move {
-                while !stop_flag.load(Ordering::Relaxed) {
-                    if let Some(res) = rx.recv().await {
-                        match res {
-                            Ok(r) => {
-                                if tx.send(Ok(NewFeedback::Review(r))).is_err() {
-                                    break;
-                                }
-                            }
-                            Err(e) => {
-                                let _ = tx.send(Err(e));
-                                break;
-                            }
-                        }
-                    } else {
+                while let Some(res) = rx.recv().await {
+                    if tx.send(res.map(NewFeedback::Review)).is_err() {
                         break;
                     }
                 }
-                stop_flag.store(true, Ordering::Relaxed);
             });
         }
 
@@ -962,29 +1180,178 @@ impl SellerClient {
     }
 }
 
-struct CircularArr<T: Copy + PartialEq, const CAP: usize> {
-    arr: [T; CAP],
-    cursor: usize,
+/// Фильтр выгрузки обратной связи, ожидающей ответа (см. `SellerClient::unanswered_feedback_stream`).
+#[derive(Debug, Clone, Default)]
+pub struct UnansweredFilter {
+    /// Тип обратной связи: `"question"` или `"review"`. `None` — любой.
+    pub kind: Option<String>,
+    /// Начало периода публикации (Unix timestamp, включительно).
+    pub date_from: Option<u64>,
+    /// Конец периода публикации (Unix timestamp, включительно).
+    pub date_to: Option<u64>,
+    /// Идентификаторы товаров (SKU Ozon или nmID Wildberries). Пустой список — все товары.
+    pub product_ids: Vec<String>,
+}
+
+impl UnansweredFilter {
+    #[inline]
+    fn includes(&self, kind: &str) -> bool {
+        self.kind.as_deref().is_none_or(|k| k == kind)
+    }
+
+    /// Проверяет товар и время публикации. Используется для методов API без серверного фильтра.
+    fn matches(&self, product_id: &str, published_at: u64) -> bool {
+        (self.product_ids.is_empty() || self.product_ids.iter().any(|id| id == product_id))
+            && self.date_from.is_none_or(|from| published_at >= from)
+            && self.date_to.is_none_or(|to| published_at <= to)
+    }
+}
+
+/// Параметры повтора запросов при ошибках: экспоненциальная пауза со случайным разбросом.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Пауза после первой ошибки.
+    pub base_delay: Duration,
+    /// Максимальная пауза. Используется также после постоянных ошибок.
+    pub max_delay: Duration,
+    /// Количество повторов в `RetryPolicy::retry`.
+    pub max_retries: u32,
 }
 
-impl<T: Copy + PartialEq, const CAP: usize> CircularArr<T, CAP> {
-    fn new() -> Self {
+impl Default for RetryPolicy {
+    fn default() -> Self {
         Self {
-            arr: [unsafe { std::mem::zeroed() }; CAP],
-            cursor: 0,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(300),
+            max_retries: 5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Пауза перед повтором после `attempt` подряд неудачных попыток (начиная с 0).
+    /// Для временных ошибок — `base_delay * 2^attempt` со случайным разбросом
+    /// в пределах половины значения, но не меньше паузы, запрошенной API.
+    /// Для постоянных ошибок — `max_delay`.
+    pub fn delay(&self, attempt: u32, e: &Error) -> Duration {
+        if !e.is_transient() {
+            return self.max_delay;
+        }
+
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.min(16)))
+            .min(self.max_delay);
+        let jittered = exp / 2 + exp.mul_f64(fastrand::f64() / 2.0);
+
+        e.retry_after().map_or(jittered, |d| d.max(jittered))
+    }
+
+    /// Выполняет запрос, повторяя его после временных ошибок не более `max_retries` раз.
+    pub async fn retry<T, F, Fut>(&self, mut f: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            match f().await {
+                Err(e) if e.is_transient() && attempt < self.max_retries => {
+                    tokio::time::sleep(self.delay(attempt, &e)).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
         }
     }
+}
+
+/// Позиция наблюдателя новой обратной связи.
+/// Наблюдатель запрашивает записи, опубликованные не раньше `last_seen`,
+/// и пропускает уже полученные записи из `seen_ids`.
+#[derive(Debug, Clone, Default)]
+pub struct ObserverCursor {
+    /// Время публикации последней полученной записи (Unix timestamp).
+    pub last_seen: u64,
+    /// Идентификаторы полученных записей, опубликованных в момент `last_seen`.
+    pub seen_ids: HashSet<String>,
+}
+
+impl ObserverCursor {
+    /// Отмечает запись полученной. Возвращает `false`, если запись уже была получена
+    /// или опубликована раньше `last_seen`.
+    /// Записи должны передаваться в порядке публикации.
+    pub fn accept(&mut self, id: &str, published_at: u64) -> bool {
+        if published_at < self.last_seen || self.seen_ids.contains(id) {
+            return false;
+        }
 
-    fn add(&mut self, value: T) {
-        self.arr[self.cursor] = value;
-        self.cursor += 1;
-        if self.cursor >= CAP {
-            self.cursor = 0;
+        if published_at > self.last_seen {
+            self.last_seen = published_at;
+            self.seen_ids.clear();
         }
+
+        self.seen_ids.insert(id.to_string());
+        true
+    }
+}
+
+fn new_question_from_ozon(q: ozmodels::Question) -> NewQuestion {
+    NewQuestion {
+        id: q.id,
+        product_id: q.sku.to_string(),
+        author_name: q.author_name,
+        text: q.text,
+        published_at: format_rfc3339_to_unix_timestamp(&q.published_at),
+    }
+}
+
+fn new_question_from_wb(q: wbmodels::Question) -> NewQuestion {
+    NewQuestion {
+        id: q.id,
+        product_id: q.product_details.nm_id.to_string(),
+        author_name: DEFAULT_AUTHOR_NAME.to_string(),
+        text: q.text,
+        published_at: format_rfc3339_to_unix_timestamp(&q.created_date),
     }
+}
 
-    fn as_slise(&self) -> &[T] {
-        self.arr.as_slice()
+fn new_review_from_ozon(r: ozmodels::Review) -> NewReview {
+    NewReview {
+        id: r.id,
+        product_id: r.sku.to_string(),
+        author_name: DEFAULT_AUTHOR_NAME.to_string(),
+        text: r.text,
+        score: r.rating as f32,
+        photos_amount: r.photos_amount as u16,
+        videos_amount: r.videos_amount as u16,
+        published_at: format_rfc3339_to_unix_timestamp(&r.published_at),
+    }
+}
+
+fn new_review_from_wb(r: wbmodels::Review) -> NewReview {
+    let mut text = "".to_string();
+
+    if !r.pros.is_empty() {
+        let _ = writeln!(&mut text, "Достоинства: {}", r.pros);
+    }
+    if !r.cons.is_empty() {
+        let _ = writeln!(&mut text, "Недостатки: {}", r.cons);
+    }
+    if !r.text.is_empty() {
+        let _ = writeln!(&mut text, "Комментарий: {}", r.text);
+    }
+
+    NewReview {
+        id: r.id,
+        product_id: r.product_details.nm_id.to_string(),
+        author_name: r.user_name,
+        text: text.trim().to_string(),
+        score: r.product_valuation as f32,
+        photos_amount: r.photo_links.map(|v| v.len()).unwrap_or(0) as u16,
+        videos_amount: r.video.map(|_| 1).unwrap_or(0) as u16,
+        published_at: format_rfc3339_to_unix_timestamp(&r.created_date),
     }
 }
 
@@ -1054,3 +1421,452 @@ fn sanitize_ozon_rich_content_json(value: &mut serde_json::Value) {
 fn sanitize_wb_rich_content_json(value: &mut serde_json::Value) {
     sanitize_rich_content_json(value, &WB_RICH_CONTENT_BLACKLIST_KEYS);
 }
+
+#[test]
+fn observer_cursor_test() {
+    let mut cursor = ObserverCursor {
+        last_seen: 100,
+        seen_ids: HashSet::from(["a".to_string()]),
+    };
+
+    assert!(!cursor.accept("a", 100));
+    assert!(!cursor.accept("old", 99));
+    assert!(cursor.accept("b", 100));
+    assert!(cursor.accept("c", 101));
+    assert!(!cursor.accept("b", 100));
+    assert!(!cursor.accept("c", 101));
+    assert_eq!(cursor.last_seen, 101);
+    assert_eq!(cursor.seen_ids.len(), 1);
+}
+
+#[test]
+fn retry_policy_delay_test() {
+    use crate::sellerapi::WbSellerApiError;
+
+    let retry = RetryPolicy {
+        base_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(10),
+        max_retries: 3,
+    };
+
+    let wb_error = |status_code, rate_limit_retry| {
+        Error::WbSellerApi(WbSellerApiError {
+            status_code,
+            rate_limit_retry,
+            detail: String::new(),
+        })
+    };
+
+    let transient = wb_error(503, None);
+    for attempt in 0..8 {
+        let delay = retry.delay(attempt, &transient);
+        let exp = (Duration::from_secs(1) * 2u32.pow(attempt)).min(retry.max_delay);
+        assert!(delay >= exp / 2 && delay <= exp);
+    }
+
+    let rate_limited = wb_error(429, Some(Duration::from_secs(7)));
+    assert!(rate_limited.is_transient());
+    assert!(retry.delay(0, &rate_limited) >= Duration::from_secs(7));
+
+    let permanent = wb_error(401, None);
+    assert!(!permanent.is_transient());
+    assert_eq!(retry.delay(0, &permanent), retry.max_delay);
+}
+
+#[tokio::test]
+async fn ozon_products_e2e_test() {
+    use crate::sellerapi::fake::{self, FakeMarketplace, FakeResponse};
+
+    let server = FakeMarketplace::start().await;
+    server.with(|d| {
+        for id in 1..=150 {
+            let name = format!("Товар {id}");
+            d.ozon_products
+                .push(fake::ozon_product(id, 1000 + id, &name, "990"));
+        }
+        d.ozon_descriptions.insert(7, "Хлопковая футболка".into());
+        d.ozon_attributes.push(fake::ozon_attributes(
+            7,
+            1007,
+            &[
+                (1, "Синий"),
+                (2, "Хлопковая футболка"),
+                (
+                    3,
+                    r#"{"content":[{"text":"Мягкая","img":"a.png"}],"version":0.3}"#,
+                ),
+            ],
+        ));
+        d.ozon_category_attributes.extend([
+            fake::ozon_category_attribute(1, "Цвет"),
+            fake::ozon_category_attribute(2, "Аннотация"),
+            fake::ozon_category_attribute(3, "Rich-контент JSON"),
+        ]);
+    });
+    let seller = server.ozon_seller();
+
+    let mut rx = seller.all_products_stream();
+    let mut ids = Vec::new();
+    while let Some(product) = rx.recv().await {
+        ids.push(product.unwrap().id);
+    }
+    let expected = (1001..=1150).map(|id| id.to_string()).collect::<Vec<_>>();
+    assert_eq!(ids, expected);
+
+    let pages = server.requests("/v3/product/list");
+    assert_eq!(pages.len(), 2);
+    assert_eq!(pages[1].body["last_id"], "100");
+
+    let info = seller.get_product_format_info("1007").await.unwrap();
+    assert_eq!(info.name, "Товар 7");
+    assert_eq!(info.price, "990 RUB");
+    assert_eq!(info.desc, "Хлопковая футболка");
+    assert_eq!(info.weight, "500g");
+    assert_eq!(info.r#box, "height: 100mm, width: 150mm, depth: 200mm");
+    assert_eq!(
+        info.attrs,
+        BTreeMap::from([
+            ("Цвет".to_string(), "Синий".to_string()),
+            (
+                "Rich-контент JSON".to_string(),
+                r#"{"content":[{"text":"Мягкая"}]}"#.to_string()
+            ),
+        ])
+    );
+
+    server.push_response(
+        "/v3/product/info/list",
+        FakeResponse::ozon_error(404, 5, "product not found"),
+    );
+    let Err(Error::OzonSellerApi(e)) = seller.get_product_price("1007").await else {
+        panic!("expected Ozon API error");
+    };
+    assert_eq!((e.status_code, e.data.code), (404, 5));
+    assert_eq!(seller.get_product_price("1007").await.unwrap().price, "990");
+}
+
+#[tokio::test]
+async fn ozon_feedback_observer_e2e_test() {
+    use crate::sellerapi::fake::{self, FakeMarketplace, FakeResponse};
+
+    let server = FakeMarketplace::start().await;
+    server.with(|d| {
+        d.ozon_questions.extend([
+            fake::ozon_question("q2", 1007, "Есть размер M?", "2025-03-02T10:00:00Z"),
+            fake::ozon_question("q1", 1007, "Какой состав?", "2025-03-01T10:00:00Z"),
+        ]);
+        d.ozon_reviews.push(fake::ozon_review(
+            "r1",
+            1007,
+            "Отличная футболка",
+            5,
+            "2025-03-01T12:00:00Z",
+        ));
+    });
+    server.push_response(
+        "/v1/question/list",
+        FakeResponse::ozon_error(429, 8, "too many requests"),
+    );
+    let seller = server.ozon_seller();
+
+    let retry = RetryPolicy {
+        base_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(100),
+        max_retries: 0,
+    };
+    let interval = Duration::from_millis(50);
+    let mut rx = seller.spawn_new_feedback_observer(
+        interval,
+        interval,
+        ObserverCursor::default(),
+        ObserverCursor::default(),
+        retry,
+    );
+
+    let (mut received, mut errors) = (Vec::new(), Vec::new());
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while received.len() < 3 {
+            match rx.recv().await.unwrap() {
+                Ok(feedback) => received.push(feedback.id().to_string()),
+                Err(e) => errors.push(e),
+            }
+        }
+    })
+    .await
+    .unwrap();
+    drop(rx);
+
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].is_transient());
+    received.retain(|id| id.starts_with('q'));
+    assert_eq!(received, ["q1", "q2"]);
+
+    seller
+        .answer_question("q1", "Хлопок 100%.", Some("1007"))
+        .await
+        .unwrap();
+    let answers = server.requests("/v1/question/answer/create");
+    assert_eq!(answers[0].body["sku"], 1007);
+    assert_eq!(answers[0].body["text"], "Хлопок 100%.");
+
+    let questions = seller
+        .get_last_new_questions(OBSERVER_BATCH_LIMIT, 0)
+        .await
+        .unwrap();
+    assert_eq!(questions.len(), 1);
+    assert_eq!(questions[0].id, "q2");
+}
+
+#[tokio::test]
+async fn wb_products_e2e_test() {
+    use crate::sellerapi::fake::{self, FakeMarketplace, FakeResponse};
+    use serde_json::json;
+
+    let server = FakeMarketplace::start().await;
+    let photo = format!("{}/vol1/part1/7/images/big/1.webp", server.url());
+    server.with(|d| {
+        for nm_id in 1..=150 {
+            let title = format!("Товар {nm_id}");
+            d.wb_cards
+                .push(fake::wb_card(nm_id, &title, "2025-02-01T00:00:00Z"));
+        }
+        let card = &mut d.wb_cards[6];
+        card["description"] = "Хлопковая футболка".into();
+        card["characteristics"] = json!([{ "id": 1, "name": "Цвет", "value": ["синий"] }]);
+        card["photos"] = json!([{
+            "big": photo,
+            "c246x328": "",
+            "c516x688": "",
+            "square": "",
+            "tm": "",
+        }]);
+        d.wb_rich_content.insert(
+            "/vol1/part1/7/info/ru/rich_v1.json".into(),
+            json!({ "content": [{ "text": "Мягкая", "style": "bold" }], "version": 1 }),
+        );
+        d.wb_goods.push(fake::wb_goods(7, 1500, 990.0));
+    });
+    let seller = server.wb_seller();
+
+    let mut rx = seller.all_products_stream();
+    let mut ids = Vec::new();
+    while let Some(product) = rx.recv().await {
+        ids.push(product.unwrap().id);
+    }
+    let expected = (1..=150).map(|id| id.to_string()).collect::<Vec<_>>();
+    assert_eq!(ids, expected);
+
+    let pages = server.requests("/content/v2/get/cards/list");
+    assert_eq!(pages.len(), 2);
+    assert_eq!(pages[1].body["settings"]["cursor"]["nmID"], 100);
+
+    let info = seller.get_product_format_info("7").await.unwrap();
+    assert_eq!(info.name, "Товар 7");
+    assert_eq!(info.price, "990 RUB");
+    assert_eq!(info.desc, "Хлопковая футболка");
+    assert_eq!(info.weight, "0.3");
+    assert_eq!(info.r#box, "height: 5, width: 20, length: 30");
+    assert_eq!(info.attrs["Цвет"], r#"["синий"]"#);
+    assert_eq!(
+        info.attrs["Rich-контент JSON"],
+        r#"{"content":[{"text":"Мягкая"}]}"#
+    );
+
+    server.push_response(
+        "/api/v2/list/goods/filter",
+        FakeResponse::wb_error(401, "unauthorized"),
+    );
+    let Err(Error::WbSellerApi(e)) = seller.get_product_price("7").await else {
+        panic!("expected WB API error");
+    };
+    assert_eq!(e.status_code, 401);
+    assert!(!Error::WbSellerApi(e).is_transient());
+}
+
+#[tokio::test]
+async fn wb_feedback_observer_e2e_test() {
+    use crate::sellerapi::fake::{self, FakeMarketplace, FakeResponse};
+
+    let server = FakeMarketplace::start().await;
+    server.with(|d| {
+        d.wb_questions.push(fake::wb_question(
+            "q1",
+            7,
+            "Какой состав?",
+            "2025-03-01T10:00:00Z",
+        ));
+        d.wb_feedbacks.extend([
+            fake::wb_feedback("r2", 7, "Села после стирки", 2, "2025-03-02T12:00:00Z"),
+            fake::wb_feedback("r1", 7, "Отличная футболка", 5, "2025-03-01T12:00:00Z"),
+        ]);
+    });
+    server.push_response("/api/v1/feedbacks", FakeResponse::rate_limited(0.05));
+    let seller = server.wb_seller();
+
+    let retry = RetryPolicy {
+        base_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(100),
+        max_retries: 0,
+    };
+    let mut rx = seller.spawn_new_review_observer(
+        Duration::from_millis(50),
+        ObserverCursor::default(),
+        retry,
+    );
+
+    let first = rx.recv().await.unwrap();
+    let Err(e) = first else {
+        panic!("expected rate limit error");
+    };
+    assert_eq!(e.retry_after(), Some(Duration::from_millis(50)));
+
+    let mut received = Vec::new();
+    for _ in 0..2 {
+        let review = rx.recv().await.unwrap().unwrap();
+        received.push((review.id, review.score));
+    }
+    drop(rx);
+    assert_eq!(received, [("r1".to_string(), 5.0), ("r2".to_string(), 2.0)]);
+
+    seller
+        .answer_review("r2", "Жаль, что так вышло.")
+        .await
+        .unwrap();
+    let reviews = seller.get_last_new_reviews(100, 0).await.unwrap();
+    assert_eq!(reviews.len(), 1);
+    assert_eq!(reviews[0].id, "r1");
+
+    server.push_response(
+        "/api/v1/questions",
+        FakeResponse::wb_data_error("invalid date range"),
+    );
+    let Err(Error::WbSellerApi(e)) = seller.get_last_new_questions(100, 0).await else {
+        panic!("expected WB API error");
+    };
+    assert!(e.detail.contains("invalid date range"));
+
+    let questions = seller.get_last_new_questions(100, 0).await.unwrap();
+    assert_eq!(questions[0].text, "Какой состав?");
+}
+
+#[tokio::test]
+async fn wb_unanswered_stream_answering_test() {
+    use crate::sellerapi::fake::{self, FakeMarketplace};
+    use hyper::Method;
+    use serde_json::json;
+
+    let server = FakeMarketplace::start().await;
+    server.with(|d| {
+        for i in 0..1500 {
+            let created = format!(
+                "2025-03-{:02}T{:02}:{:02}:00Z",
+                1 + i / 1440,
+                i / 60 % 24,
+                i % 60
+            );
+            let id = format!("q{i}");
+            d.wb_questions
+                .push(fake::wb_question(&id, 7, "Какой состав?", &created));
+        }
+    });
+    let seller = server.wb_seller();
+
+    // Получатель отвечает на каждый вопрос, убирая его из выдачи неотвеченных.
+    let mut rx = seller.unanswered_feedback_stream(
+        UnansweredFilter {
+            kind: Some("question".into()),
+            ..Default::default()
+        },
+        RetryPolicy::default(),
+    );
+    let mut ids = Vec::new();
+    while let Some(feedback) = rx.recv().await {
+        let id = feedback.unwrap().id().to_string();
+        server.with(|d| {
+            let q = d.wb_questions.iter_mut().find(|q| q["id"] == id).unwrap();
+            q["answer"] = json!({ "text": "Хлопок.", "editable": true, "createDate": "" });
+        });
+        ids.push(id);
+    }
+
+    let expected = (0..1500).map(|i| format!("q{i}")).collect::<Vec<_>>();
+    assert_eq!(ids, expected);
+
+    let pages = server
+        .requests("/api/v1/questions")
+        .into_iter()
+        .filter(|r| r.method == Method::GET)
+        .collect::<Vec<_>>();
+    assert_eq!(pages.len(), 2);
+    assert_eq!(pages[1].query_param("skip"), Some("1000"));
+}
+
+#[tokio::test]
+async fn feedback_observer_pages_test() {
+    use crate::sellerapi::fake::{self, FakeMarketplace};
+
+    let created = |i: usize| format!("2025-03-01T{:02}:{:02}:00Z", i / 60, i % 60);
+    let expected = |prefix: &str| (0..250).map(|i| format!("{prefix}{i}")).collect::<Vec<_>>();
+
+    let server = FakeMarketplace::start().await;
+    server.with(|d| {
+        for i in (0..250).rev() {
+            let (q, r) = (format!("q{i}"), format!("r{i}"));
+            d.ozon_questions
+                .push(fake::ozon_question(&q, 1007, "Какой состав?", &created(i)));
+            d.ozon_reviews
+                .push(fake::ozon_review(&r, 1007, "Отлично", 5, &created(i)));
+            d.wb_questions
+                .push(fake::wb_question(&q, 7, "Какой состав?", &created(i)));
+        }
+    });
+
+    let collect = |seller: SellerClient, count: usize| async move {
+        let interval = Duration::from_secs(60);
+        let mut rx = seller.spawn_new_feedback_observer(
+            interval,
+            interval,
+            ObserverCursor::default(),
+            ObserverCursor::default(),
+            RetryPolicy::default(),
+        );
+
+        let (mut questions, mut reviews) = (Vec::new(), Vec::new());
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while questions.len() + reviews.len() < count {
+                match rx.recv().await.unwrap().unwrap() {
+                    NewFeedback::Question(q) => questions.push(q.id),
+                    NewFeedback::Review(r) => reviews.push(r.id),
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        (questions, reviews)
+    };
+
+    let (questions, reviews) = collect(server.ozon_seller(), 500).await;
+    assert_eq!(questions, expected("q"));
+    assert_eq!(reviews, expected("r"));
+    assert_eq!(server.requests("/v1/question/list").len(), 4);
+    assert_eq!(server.requests("/v1/review/list").len(), 3);
+
+    // Отзывы Ozon запрашиваются от новых к старым до первого отзыва старше `date_from`.
+    let date_from = format_rfc3339_to_unix_timestamp(&created(150));
+    let reviews = server
+        .ozon_seller()
+        .get_last_new_reviews(OBSERVER_BATCH_LIMIT, date_from)
+        .await
+        .unwrap();
+    assert_eq!(reviews.len(), 100);
+    assert_eq!(reviews[0].id, "r150");
+    assert_eq!(server.requests("/v1/review/list").len(), 5);
+
+    let (questions, _) = collect(server.wb_seller(), 250).await;
+    assert_eq!(questions, expected("q"));
+    let pages = server.requests("/api/v1/questions");
+    assert_eq!(pages.len(), 3);
+    assert_eq!(pages[2].query_param("skip"), Some("200"));
+    assert_eq!(pages[2].query_param("order"), Some("dateAsc"));
+}
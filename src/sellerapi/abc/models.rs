@@ -2,6 +2,8 @@ use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 
+use crate::sellerapi::Fnv1a;
+
 pub const DEFAULT_AUTHOR_NAME: &str = "User";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +33,42 @@ pub enum NewFeedback {
     Question(NewQuestion),
 }
 
+impl NewFeedback {
+    #[inline]
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Review(r) => &r.id,
+            Self::Question(q) => &q.id,
+        }
+    }
+
+    #[inline]
+    pub fn product_id(&self) -> &str {
+        match self {
+            Self::Review(r) => &r.product_id,
+            Self::Question(q) => &q.product_id,
+        }
+    }
+
+    /// Время публикации (Unix timestamp).
+    #[inline]
+    pub fn published_at(&self) -> u64 {
+        match self {
+            Self::Review(r) => r.published_at,
+            Self::Question(q) => q.published_at,
+        }
+    }
+
+    /// Строковый тип обратной связи: `"question"` или `"review"`.
+    #[inline]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Review(_) => "review",
+            Self::Question(_) => "question",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Product {
     pub id: String,
@@ -47,3 +85,34 @@ pub struct ProductFormatInfo {
     pub weight: String,
     pub r#box: String,
 }
+
+impl ProductFormatInfo {
+    /// Хэш данных товара, по которому определяется, изменился ли товар.
+    pub fn content_hash(&self) -> String {
+        let mut hash = Fnv1a::default();
+        hash.write(&serde_json::to_vec(self).unwrap_or_default());
+
+        format!("{:016x}", hash.0)
+    }
+}
+
+/// Цена товара.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductPrice {
+    pub id: String,
+    /// Цена для покупателя с учётом скидок и акций.
+    pub price: String,
+    /// Цена без скидок.
+    pub old_price: String,
+    pub currency: String,
+}
+
+/// Остатки товара, доступные для продажи.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductStock {
+    pub id: String,
+    /// Общий остаток.
+    pub available: i64,
+    /// Остатки по складам (Wildberries) или схемам работы (Ozon: fbo, fbs, ...).
+    pub warehouses: BTreeMap<String, i64>,
+}
@@ -0,0 +1,906 @@
+//! Локальный HTTP сервер, эмулирующий методы Ozon Seller API и WB Seller API для тестов.
+//!
+//! Сервер отвечает на запросы клиентов по данным `FakeData`: товары, вопросы и отзывы
+//! Ozon, карточки, цены, остатки, вопросы и отзывы Wildberries, с постраничной выдачей,
+//! как у настоящего API. Ответы из очереди `FakeMarketplace::push_response` (ошибки,
+//! 429 с `X-Ratelimit-Retry`) отдаются раньше эмулированных.
+
+use crate::sellerapi::{OzonApiUrls, OzonSellerClient, SellerClient, WbApiUrls, WbSellerClient};
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    Method, Request, Response,
+    body::{self, Bytes},
+    service::service_fn,
+};
+use hyper_util::rt::TokioIo;
+use serde_json::{Value, json};
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use tokio::{net::TcpListener, task::JoinHandle};
+
+/// Размер страницы списка вопросов Ozon (`/v1/question/list`).
+const OZON_QUESTION_PAGE_LIMIT: usize = 100;
+
+/// Данные, по которым сервер формирует ответы. Элементы — JSON объекты в формате API,
+/// их удобно создавать функциями `ozon_product`, `wb_card` и т.п.
+#[derive(Debug, Default)]
+pub struct FakeData {
+    /// Товары Ozon (`/v3/product/info/list`).
+    pub ozon_products: Vec<Value>,
+    /// Описания товаров Ozon по `product_id`.
+    pub ozon_descriptions: HashMap<i64, String>,
+    /// Характеристики товаров Ozon (`/v4/product/info/attributes`).
+    pub ozon_attributes: Vec<Value>,
+    /// Характеристики категории Ozon (`/v1/description-category/attribute`).
+    pub ozon_category_attributes: Vec<Value>,
+    /// Вопросы Ozon.
+    pub ozon_questions: Vec<Value>,
+    /// Отзывы Ozon.
+    pub ozon_reviews: Vec<Value>,
+    /// Карточки товаров Wildberries в порядке выдачи.
+    pub wb_cards: Vec<Value>,
+    /// Цены товаров Wildberries.
+    pub wb_goods: Vec<Value>,
+    /// Остатки на складах Wildberries.
+    pub wb_stocks: Vec<Value>,
+    /// Rich-контент Wildberries по пути запроса (`/vol1/.../info/ru/rich_v1.json`).
+    pub wb_rich_content: HashMap<String, Value>,
+    /// Вопросы Wildberries.
+    pub wb_questions: Vec<Value>,
+    /// Отзывы Wildberries.
+    pub wb_feedbacks: Vec<Value>,
+}
+
+/// Запрос, полученный сервером.
+#[derive(Debug, Clone)]
+pub struct FakeRequest {
+    pub method: Method,
+    pub path: String,
+    pub query: String,
+    /// Тело запроса, `Value::Null`, если тело пустое.
+    pub body: Value,
+}
+
+impl FakeRequest {
+    /// Значение параметра строки запроса.
+    pub fn query_param(&self, key: &str) -> Option<&str> {
+        self.query
+            .split('&')
+            .filter_map(|kv| kv.split_once('='))
+            .find_map(|(k, v)| (k == key).then_some(v))
+    }
+}
+
+/// Ответ сервера.
+#[derive(Debug, Clone)]
+pub struct FakeResponse {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+impl FakeResponse {
+    pub fn json(status: u16, body: &Value) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type", "application/json".into())],
+            body: body.to_string(),
+        }
+    }
+
+    /// Ошибка Ozon Seller API: `{ code, message, details }`.
+    pub fn ozon_error(status: u16, code: i32, message: &str) -> Self {
+        Self::json(
+            status,
+            &json!({ "code": code, "message": message, "details": [] }),
+        )
+    }
+
+    /// Ошибка WB Seller API с HTTP статусом `status`.
+    pub fn wb_error(status: u16, detail: &str) -> Self {
+        Self::json(
+            status,
+            &json!({ "title": "error", "detail": detail, "status": status }),
+        )
+    }
+
+    /// Ответ WB Seller API с признаком ошибки: `{ data: null, error: true, errorText }`.
+    pub fn wb_data_error(text: &str) -> Self {
+        Self::json(
+            200,
+            &json!({
+                "data": null,
+                "error": true,
+                "errorText": text,
+                "additionalErrors": [text],
+            }),
+        )
+    }
+
+    /// Превышение лимита запросов с паузой `retry_secs` в заголовке `X-Ratelimit-Retry`.
+    pub fn rate_limited(retry_secs: f64) -> Self {
+        let mut res = Self::wb_error(429, "too many requests");
+        res.headers
+            .push(("X-Ratelimit-Retry", retry_secs.to_string()));
+        res
+    }
+}
+
+#[derive(Debug, Default)]
+struct FakeState {
+    data: FakeData,
+    /// Очереди заданных ответов по пути запроса.
+    responses: HashMap<String, VecDeque<FakeResponse>>,
+    requests: Vec<FakeRequest>,
+}
+
+/// Запущенный сервер. Останавливается при удалении.
+pub struct FakeMarketplace {
+    addr: SocketAddr,
+    state: Arc<Mutex<FakeState>>,
+    task: JoinHandle<()>,
+}
+
+impl FakeMarketplace {
+    /// Запускает сервер на свободном локальном порту.
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(Mutex::new(FakeState::default()));
+
+        let task = {
+            let state = state.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        let service = service_fn(move |req| handler(req, state.clone()));
+                        let _ = hyper::server::conn::http1::Builder::new()
+                            .serve_connection(TokioIo::new(stream), service)
+                            .await;
+                    });
+                }
+            })
+        };
+
+        Self { addr, state, task }
+    }
+
+    /// Базовый URL сервера.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Клиент Ozon, отправляющий запросы на этот сервер.
+    pub fn ozon_seller(&self) -> SellerClient {
+        let cli = OzonSellerClient::new("1".into(), "key".into())
+            .with_urls(OzonApiUrls { seller: self.url() });
+        SellerClient::Ozon(Arc::new(cli))
+    }
+
+    /// Клиент Wildberries, отправляющий запросы всех хостов API на этот сервер.
+    pub fn wb_seller(&self) -> SellerClient {
+        let cli = WbSellerClient::new("token".into()).with_urls(WbApiUrls::all(&self.url()));
+        SellerClient::Wb(Arc::new(cli))
+    }
+
+    /// Изменяет данные сервера.
+    pub fn with<R>(&self, f: impl FnOnce(&mut FakeData) -> R) -> R {
+        f(&mut self.state.lock().unwrap().data)
+    }
+
+    /// Добавляет ответ на следующий запрос к `path`. Ответы отдаются по очереди,
+    /// после чего запросы снова обрабатываются по данным сервера.
+    pub fn push_response(&self, path: &str, response: FakeResponse) {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .entry(path.into())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// Полученные запросы к `path` в порядке поступления.
+    pub fn requests(&self, path: &str) -> Vec<FakeRequest> {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|r| r.path == path)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Drop for FakeMarketplace {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn handler(
+    req: Request<body::Incoming>,
+    state: Arc<Mutex<FakeState>>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let (parts, body) = req.into_parts();
+    let body = body
+        .collect()
+        .await
+        .map(|b| b.to_bytes())
+        .unwrap_or_default();
+
+    let req = FakeRequest {
+        method: parts.method,
+        path: parts.uri.path().to_string(),
+        query: parts.uri.query().unwrap_or_default().to_string(),
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+    };
+
+    let res = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(req.clone());
+
+        match state
+            .responses
+            .get_mut(&req.path)
+            .and_then(|q| q.pop_front())
+        {
+            Some(res) => res,
+            None => route(&mut state.data, &req),
+        }
+    };
+
+    let mut builder = Response::builder().status(res.status);
+    for (name, value) in res.headers {
+        builder = builder.header(name, value);
+    }
+    Ok(builder.body(Full::new(Bytes::from(res.body))).unwrap())
+}
+
+fn route(data: &mut FakeData, req: &FakeRequest) -> FakeResponse {
+    let body = &req.body;
+
+    match (&req.method, req.path.as_str()) {
+        (&Method::POST, "/v3/product/list") => ozon_product_list(data, body),
+        (&Method::POST, "/v3/product/info/list") => {
+            let items = data
+                .ozon_products
+                .iter()
+                .filter(|p| {
+                    ["product_id", "sku", "offer_id"].iter().any(|key| {
+                        let field = if *key == "product_id" { "id" } else { key };
+                        contains_id(&body[key], &p[field])
+                    })
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+
+            FakeResponse::json(200, &json!({ "items": items }))
+        }
+        (&Method::POST, "/v1/product/info/description") => {
+            let id = body["product_id"].as_i64().unwrap_or_default();
+            let Some(product) = data.ozon_products.iter().find(|p| p["id"] == id) else {
+                return FakeResponse::ozon_error(404, 5, "product not found");
+            };
+
+            FakeResponse::json(
+                200,
+                &json!({
+                    "result": {
+                        "id": id,
+                        "offer_id": product["offer_id"],
+                        "name": product["name"],
+                        "description": data.ozon_descriptions.get(&id).cloned().unwrap_or_default(),
+                    }
+                }),
+            )
+        }
+        (&Method::POST, "/v4/product/info/attributes") => {
+            let filter = &body["filter"];
+            let result = data
+                .ozon_attributes
+                .iter()
+                .filter(|a| {
+                    contains_id(&filter["sku"], &a["sku"])
+                        || contains_id(&filter["product_id"], &a["id"])
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+
+            FakeResponse::json(
+                200,
+                &json!({ "result": result, "total": result.len(), "last_id": "" }),
+            )
+        }
+        (&Method::POST, "/v1/description-category/attribute") => {
+            FakeResponse::json(200, &json!({ "result": data.ozon_category_attributes }))
+        }
+        (&Method::POST, "/v1/question/list") => {
+            let filter = &body["filter"];
+            let status = filter["status"].as_str().unwrap_or("ALL");
+            let date_from = filter["date_from"].as_str().map(rfc3339_to_unix);
+
+            let questions = data
+                .ozon_questions
+                .iter()
+                .filter(|q| status == "ALL" || q["status"] == status)
+                .filter(|q| date_from.is_none_or(|from| published_at(q) >= from))
+                .collect::<Vec<_>>();
+
+            let start = match body["last_id"].as_str().filter(|s| !s.is_empty()) {
+                Some(last_id) => questions
+                    .iter()
+                    .position(|q| q["id"] == last_id)
+                    .map_or(questions.len(), |i| i + 1),
+                None => 0,
+            };
+            let page = questions
+                .iter()
+                .skip(start)
+                .take(OZON_QUESTION_PAGE_LIMIT)
+                .copied()
+                .cloned()
+                .collect::<Vec<_>>();
+            let last_id = page.last().map(|q| q["id"].clone()).unwrap_or("".into());
+
+            FakeResponse::json(200, &json!({ "questions": page, "last_id": last_id }))
+        }
+        (&Method::POST, "/v1/question/answer/create") => {
+            let id = body["question_id"].as_str().unwrap_or_default();
+            let Some(question) = data.ozon_questions.iter_mut().find(|q| q["id"] == id) else {
+                return FakeResponse::ozon_error(404, 5, "question not found");
+            };
+            question["status"] = "PROCESSED".into();
+            question["answers_count"] = 1.into();
+
+            FakeResponse::json(200, &json!({ "answer_id": format!("answer-{id}") }))
+        }
+        (&Method::POST, "/v1/review/list") => ozon_review_list(data, body),
+        (&Method::POST, "/v1/review/comment/create") => {
+            let id = body["review_id"].as_str().unwrap_or_default();
+            let Some(review) = data.ozon_reviews.iter_mut().find(|r| r["id"] == id) else {
+                return FakeResponse::ozon_error(404, 5, "review not found");
+            };
+            review["comments_amount"] = 1.into();
+            if body["mark_review_as_processed"] == true {
+                review["status"] = "PROCESSED".into();
+            }
+
+            FakeResponse::json(200, &json!({ "comment_id": format!("comment-{id}") }))
+        }
+        (&Method::POST, "/content/v2/get/cards/list") => wb_cards_list(data, body),
+        (&Method::GET, "/api/v2/list/goods/filter") => {
+            let limit = query_num(req, "limit").unwrap_or(10) as usize;
+            let offset = query_num(req, "offset").unwrap_or(0) as usize;
+            let nm_id = query_num(req, "filterNmID");
+
+            let goods = data
+                .wb_goods
+                .iter()
+                .filter(|g| nm_id.is_none_or(|id| g["nmID"] == id))
+                .skip(offset)
+                .take(limit)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            FakeResponse::json(200, &json!({ "data": { "listGoods": goods } }))
+        }
+        (&Method::GET, "/api/v1/supplier/stocks") => {
+            FakeResponse::json(200, &json!(data.wb_stocks))
+        }
+        (&Method::GET, "/api/v1/questions") => FakeResponse::json(
+            200,
+            &wb_envelope(wb_page(&data.wb_questions, "questions", req)),
+        ),
+        (&Method::PATCH, "/api/v1/questions") => {
+            let id = body["id"].as_str().unwrap_or_default();
+            let Some(question) = data.wb_questions.iter_mut().find(|q| q["id"] == id) else {
+                return FakeResponse::wb_error(404, "question not found");
+            };
+            question["wasViewed"] = true.into();
+            if let Some(text) = body["answer"]["text"].as_str() {
+                question["answer"] = json!({ "text": text, "editable": true, "createDate": "" });
+                question["state"] = body["state"].clone();
+            }
+
+            FakeResponse::json(200, &wb_envelope(json!({})))
+        }
+        (&Method::GET, "/api/v1/feedbacks") => FakeResponse::json(
+            200,
+            &wb_envelope(wb_page(&data.wb_feedbacks, "feedbacks", req)),
+        ),
+        (&Method::POST, "/api/v1/feedbacks/answer") => {
+            let id = body["id"].as_str().unwrap_or_default();
+            let Some(feedback) = data.wb_feedbacks.iter_mut().find(|r| r["id"] == id) else {
+                return FakeResponse::wb_error(404, "feedback not found");
+            };
+            feedback["answer"] = json!({ "text": body["text"], "state": "wbRu", "editable": true });
+            feedback["state"] = "wbRu".into();
+
+            FakeResponse {
+                status: 204,
+                headers: Vec::new(),
+                body: String::new(),
+            }
+        }
+        (&Method::GET, path) if path.ends_with("/info/ru/rich_v1.json") => {
+            match data.wb_rich_content.get(path) {
+                Some(content) => FakeResponse::json(200, content),
+                None => FakeResponse::wb_error(404, "not found"),
+            }
+        }
+        (method, path) => FakeResponse::wb_error(404, &format!("unknown method {method} {path}")),
+    }
+}
+
+/// `/v3/product/list`: товары по порядку, страница начинается после `last_id`.
+fn ozon_product_list(data: &FakeData, body: &Value) -> FakeResponse {
+    let limit = body["limit"].as_u64().unwrap_or(100) as usize;
+    let start = match body["last_id"].as_str().filter(|s| !s.is_empty()) {
+        Some(last_id) => data
+            .ozon_products
+            .iter()
+            .position(|p| p["id"].as_i64() == last_id.parse().ok())
+            .map_or(data.ozon_products.len(), |i| i + 1),
+        None => 0,
+    };
+
+    let items = data
+        .ozon_products
+        .iter()
+        .skip(start)
+        .take(limit)
+        .map(|p| {
+            json!({
+                "archived": false,
+                "has_fbo_stocks": false,
+                "has_fbs_stocks": false,
+                "is_discounted": false,
+                "offer_id": p["offer_id"],
+                "product_id": p["id"],
+                "quants": [],
+            })
+        })
+        .collect::<Vec<_>>();
+    let last_id = items
+        .last()
+        .map(|i| i["product_id"].to_string())
+        .unwrap_or_default();
+
+    FakeResponse::json(
+        200,
+        &json!({
+            "result": {
+                "items": items,
+                "total": data.ozon_products.len(),
+                "last_id": last_id,
+            }
+        }),
+    )
+}
+
+/// `/v1/review/list`: отзывы по статусу и дате публикации, страница начинается после `last_id`.
+fn ozon_review_list(data: &FakeData, body: &Value) -> FakeResponse {
+    let limit = body["limit"].as_u64().unwrap_or(100) as usize;
+    let status = body["status"].as_str().unwrap_or("ALL");
+
+    let mut reviews = data
+        .ozon_reviews
+        .iter()
+        .filter(|r| status == "ALL" || r["status"] == status)
+        .collect::<Vec<_>>();
+    reviews.sort_by_key(|r| published_at(r));
+    if body["sort_dir"] == "DESC" {
+        reviews.reverse();
+    }
+
+    let start = match body["last_id"].as_str().filter(|s| !s.is_empty()) {
+        Some(last_id) => reviews
+            .iter()
+            .position(|r| r["id"] == last_id)
+            .map_or(reviews.len(), |i| i + 1),
+        None => 0,
+    };
+    let page = reviews
+        .iter()
+        .skip(start)
+        .take(limit)
+        .copied()
+        .cloned()
+        .collect::<Vec<_>>();
+    let last_id = page.last().map(|r| r["id"].clone()).unwrap_or("".into());
+
+    FakeResponse::json(
+        200,
+        &json!({
+            "reviews": page,
+            "last_id": last_id,
+            "has_next": start + page.len() < reviews.len(),
+        }),
+    )
+}
+
+/// `/content/v2/get/cards/list`: карточки по порядку, страница начинается после карточки
+/// курсора (`updatedAt`, `nmID`).
+fn wb_cards_list(data: &FakeData, body: &Value) -> FakeResponse {
+    let settings = &body["settings"];
+    let cursor = &settings["cursor"];
+    let limit = cursor["limit"].as_u64().unwrap_or(100) as usize;
+    let text_search = settings["filter"]["textSearch"].as_str();
+
+    let cards = data
+        .wb_cards
+        .iter()
+        .filter(|c| {
+            text_search.is_none_or(|s| c["nmID"].as_i64() == s.parse().ok() || c["vendorCode"] == s)
+        })
+        .collect::<Vec<_>>();
+
+    let start = match cursor["nmID"].as_i64() {
+        Some(nm_id) => cards
+            .iter()
+            .position(|c| c["nmID"] == nm_id && c["updatedAt"] == cursor["updatedAt"])
+            .map_or(cards.len(), |i| i + 1),
+        None => 0,
+    };
+    let page = cards
+        .iter()
+        .skip(start)
+        .take(limit)
+        .copied()
+        .cloned()
+        .collect::<Vec<_>>();
+    let last = page.last();
+
+    FakeResponse::json(
+        200,
+        &json!({
+            "cards": page,
+            "cursor": {
+                "updatedAt": last.map(|c| c["updatedAt"].clone()),
+                "nmID": last.map_or(0.into(), |c| c["nmID"].clone()),
+                "total": page.len(),
+            }
+        }),
+    )
+}
+
+/// Данные страницы вопросов или отзывов Wildberries (список в поле `key`) по параметрам
+/// `isAnswered`, `nmId`, `dateFrom`, `dateTo`, `order`, `skip` и `take`.
+fn wb_page(items: &[Value], key: &str, req: &FakeRequest) -> Value {
+    let is_answered = req.query_param("isAnswered") == Some("true");
+    let nm_id = query_num(req, "nmId");
+    let date_from = query_num(req, "dateFrom");
+    let date_to = query_num(req, "dateTo");
+    let skip = query_num(req, "skip").unwrap_or(0) as usize;
+    let take = query_num(req, "take").unwrap_or(10) as usize;
+
+    let unanswered = items.iter().filter(|i| i["answer"].is_null()).count();
+
+    let mut filtered = items
+        .iter()
+        .filter(|i| i["answer"].is_null() != is_answered)
+        .filter(|i| nm_id.is_none_or(|id| i["productDetails"]["nmId"] == id))
+        .filter(|i| {
+            let created = rfc3339_to_unix(i["createdDate"].as_str().unwrap_or_default());
+            date_from.is_none_or(|from| created >= from) && date_to.is_none_or(|to| created <= to)
+        })
+        .collect::<Vec<_>>();
+    filtered.sort_by_key(|i| rfc3339_to_unix(i["createdDate"].as_str().unwrap_or_default()));
+    if req.query_param("order") != Some("dateAsc") {
+        filtered.reverse();
+    }
+    let page = filtered
+        .into_iter()
+        .skip(skip)
+        .take(take)
+        .collect::<Vec<_>>();
+
+    json!({
+        key: page,
+        "countUnanswered": unanswered,
+        "countArchive": items.len() - unanswered,
+    })
+}
+
+fn wb_envelope(data: Value) -> Value {
+    json!({
+        "data": data,
+        "error": false,
+        "errorText": "",
+        "additionalErrors": null,
+    })
+}
+
+/// Проверяет, что список идентификаторов `ids` (строки или числа) содержит `id`.
+fn contains_id(ids: &Value, id: &Value) -> bool {
+    let id = id.as_str().map_or_else(|| id.to_string(), str::to_string);
+    ids.as_array().is_some_and(|ids| {
+        ids.iter()
+            .any(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string) == id)
+    })
+}
+
+fn query_num(req: &FakeRequest, key: &str) -> Option<i64> {
+    req.query_param(key)?.parse().ok()
+}
+
+fn published_at(v: &Value) -> i64 {
+    rfc3339_to_unix(v["published_at"].as_str().unwrap_or_default())
+}
+
+fn rfc3339_to_unix(s: &str) -> i64 {
+    OffsetDateTime::parse(s, &Rfc3339)
+        .map(|v| v.unix_timestamp())
+        .unwrap_or_default()
+}
+
+/// Товар Ozon в формате `/v3/product/info/list`.
+pub fn ozon_product(id: i64, sku: i64, name: &str, price: &str) -> Value {
+    let statuses = json!({
+        "is_created": true,
+        "moderate_status": "approved",
+        "status": "price_sent",
+        "status_description": "",
+        "status_failed": "",
+        "status_name": "Продаётся",
+        "status_tooltip": "",
+        "status_updated_at": "2025-01-01T00:00:00Z",
+        "validation_status": "success",
+    });
+    let flags = json!({
+        "has_discounted_fbo_item": false,
+        "is_archived": false,
+        "is_autoarchived": false,
+        "is_discounted": false,
+        "is_kgt": false,
+        "is_prepayment_allowed": false,
+        "is_super": false,
+    });
+
+    let mut product = json!({
+        "id": id,
+        "sku": sku,
+        "offer_id": format!("offer-{id}"),
+        "name": name,
+        "created_at": "2025-01-01T00:00:00Z",
+        "updated_at": "2025-01-01T00:00:00Z",
+        "currency_code": "RUB",
+        "description_category_id": 1,
+        "discounted_fbo_stocks": 0,
+        "marketing_price": price,
+        "min_price": price,
+        "old_price": price,
+        "price": price,
+        "type_id": 1,
+        "vat": "0.2",
+        "volume_weight": 0.5,
+        "barcodes": [],
+        "color_image": [],
+        "images": [],
+        "images360": [],
+        "primary_image": [],
+        "commissions": [],
+        "errors": [],
+        "model_info": { "count": 1, "model_id": id },
+        "price_indexes": { "color_index": "COLOR_INDEX_GREEN" },
+        "promotions": [],
+        "sources": [],
+        "statuses": statuses,
+        "stocks": { "has_stock": false, "stocks": [] },
+        "visibility_details": { "has_price": true, "has_stock": false },
+    });
+    if let (Some(product), Value::Object(flags)) = (product.as_object_mut(), flags) {
+        product.extend(flags);
+    }
+    product
+}
+
+/// Характеристики товара Ozon в формате `/v4/product/info/attributes`.
+/// `attrs` — пары (идентификатор характеристики, значение).
+pub fn ozon_attributes(id: i64, sku: i64, attrs: &[(i64, &str)]) -> Value {
+    let attributes = attrs
+        .iter()
+        .map(|(id, value)| {
+            json!({
+                "id": id,
+                "complex_id": 0,
+                "values": [{ "dictionary_value_id": 0, "value": value }],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "id": id,
+        "barcode": "",
+        "barcodes": [],
+        "name": "",
+        "offer_id": format!("offer-{id}"),
+        "type_id": 1,
+        "height": 100,
+        "depth": 200,
+        "width": 150,
+        "dimension_unit": "mm",
+        "weight": 500,
+        "weight_unit": "g",
+        "primary_image": "",
+        "sku": sku,
+        "model_info": { "count": 1, "model_id": id },
+        "images": [],
+        "pdf_list": [],
+        "attributes": attributes,
+        "attributes_with_defaults": [],
+        "complex_attributes": [],
+        "color_image": "",
+        "description_category_id": 1,
+    })
+}
+
+/// Характеристика категории Ozon в формате `/v1/description-category/attribute`.
+pub fn ozon_category_attribute(id: i64, name: &str) -> Value {
+    json!({
+        "id": id,
+        "attribute_complex_id": 0,
+        "name": name,
+        "description": "",
+        "type": "String",
+        "is_collection": false,
+        "is_required": false,
+        "is_aspect": false,
+        "max_value_count": 0,
+        "group_name": "",
+        "group_id": 0,
+        "dictionary_id": 0,
+        "category_dependent": false,
+        "complex_is_collection": false,
+    })
+}
+
+/// Необработанный вопрос Ozon в формате `/v1/question/list`.
+pub fn ozon_question(id: &str, sku: i64, text: &str, published_at: &str) -> Value {
+    json!({
+        "answers_count": 0,
+        "author_name": "Покупатель",
+        "id": id,
+        "product_url": "",
+        "published_at": published_at,
+        "question_link": "",
+        "sku": sku,
+        "status": "UNPROCESSED",
+        "text": text,
+    })
+}
+
+/// Необработанный отзыв Ozon в формате `/v1/review/list`.
+pub fn ozon_review(id: &str, sku: i64, text: &str, rating: i32, published_at: &str) -> Value {
+    json!({
+        "comments_amount": 0,
+        "id": id,
+        "is_rating_participant": true,
+        "order_status": "DELIVERED",
+        "photos_amount": 0,
+        "published_at": published_at,
+        "rating": rating,
+        "sku": sku,
+        "status": "UNPROCESSED",
+        "text": text,
+        "videos_amount": 0,
+    })
+}
+
+/// Карточка товара Wildberries в формате `/content/v2/get/cards/list`.
+pub fn wb_card(nm_id: i64, title: &str, updated_at: &str) -> Value {
+    json!({
+        "nmID": nm_id,
+        "imtID": nm_id,
+        "nmUUID": format!("uuid-{nm_id}"),
+        "subjectID": 1,
+        "subjectName": "Футболки",
+        "vendorCode": format!("vendor-{nm_id}"),
+        "brand": "Blueberry",
+        "title": title,
+        "description": "",
+        "needKiz": false,
+        "photos": [],
+        "dimensions": {
+            "length": 30,
+            "width": 20,
+            "height": 5,
+            "weightBrutto": 0.3,
+            "isValid": true,
+        },
+        "characteristics": [],
+        "sizes": [],
+        "createdAt": "2025-01-01T00:00:00Z",
+        "updatedAt": updated_at,
+    })
+}
+
+/// Цены товара Wildberries в формате `/api/v2/list/goods/filter`.
+pub fn wb_goods(nm_id: i64, price: i64, discounted_price: f64) -> Value {
+    json!({
+        "nmID": nm_id,
+        "vendorCode": format!("vendor-{nm_id}"),
+        "sizes": [{
+            "sizeID": 1,
+            "price": price,
+            "discountedPrice": discounted_price,
+            "clubDiscountedPrice": discounted_price,
+            "techSizeName": "0",
+        }],
+        "currencyIsoCode4217": "RUB",
+        "discount": 0,
+        "clubDiscount": 0,
+        "editableSizePrice": false,
+    })
+}
+
+/// Необработанный вопрос Wildberries в формате `/api/v1/questions`.
+pub fn wb_question(id: &str, nm_id: i64, text: &str, created_date: &str) -> Value {
+    json!({
+        "id": id,
+        "text": text,
+        "createdDate": created_date,
+        "state": "suppliersPortalSynch",
+        "answer": null,
+        "productDetails": {
+            "imtId": nm_id,
+            "nmId": nm_id,
+            "productName": "",
+            "supplierArticle": "",
+            "supplierName": "",
+            "brandName": "",
+        },
+        "wasViewed": false,
+        "isWarned": false,
+    })
+}
+
+/// Необработанный отзыв Wildberries в формате `/api/v1/feedbacks`.
+pub fn wb_feedback(id: &str, nm_id: i64, text: &str, valuation: i32, created_date: &str) -> Value {
+    json!({
+        "id": id,
+        "text": text,
+        "pros": "",
+        "cons": "",
+        "productValuation": valuation,
+        "createdDate": created_date,
+        "answer": null,
+        "state": "none",
+        "productDetails": {
+            "imtId": nm_id,
+            "nmId": nm_id,
+            "productName": "",
+            "supplierArticle": null,
+            "supplierName": null,
+            "brandName": null,
+            "size": "0",
+        },
+        "video": null,
+        "wasViewed": false,
+        "photoLinks": null,
+        "userName": "Покупатель",
+        "matchingSize": "",
+        "isAbleSupplierFeedbackValuation": false,
+        "supplierFeedbackValuation": 0,
+        "isAbleSupplierProductValuation": false,
+        "supplierProductValuation": 0,
+        "isAbleReturnProductOrders": false,
+        "returnProductOrdersDate": null,
+        "bables": null,
+        "lastOrderShkId": 0,
+        "lastOrderCreatedAt": created_date,
+        "color": "",
+        "subjectId": 1,
+        "subjectName": "Футболки",
+        "parentFeedbackId": null,
+        "childFeedbackId": null,
+    })
+}
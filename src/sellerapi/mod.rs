@@ -1,7 +1,12 @@
 mod abc;
+mod cassette;
 mod ozon;
 mod wb;
 
+#[cfg(test)]
+pub mod fake;
+
 pub use abc::*;
+pub use cassette::*;
 pub use ozon::*;
 pub use wb::*;
@@ -1,5 +1,9 @@
 use super::models;
-use crate::error::Result;
+use crate::{
+    config::OzonSellerCredentials,
+    error::Result,
+    sellerapi::{Cassette, CassetteMode},
+};
 use reqwest::{IntoUrl, Method, header::HeaderMap};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::{Value, json};
@@ -13,7 +17,6 @@ use std::{
 };
 use thiserror::Error as ThisError;
 
-// const BASE_URL: &str = "https://api-seller.ozon.ru";
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
 const LIMIT_OF_REQUESTS_PER_SECOND: u32 = 42;
 
@@ -38,40 +41,94 @@ pub struct OzonSellerApiErrorDetail {
     pub value: String,
 }
 
+/// Базовые URL хостов Ozon Seller API. Переопределяются, например, для локального
+/// тестового сервера.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OzonApiUrls {
+    /// Seller API: товары, отзывы, вопросы, цены и остатки.
+    pub seller: String,
+}
+
+impl Default for OzonApiUrls {
+    fn default() -> Self {
+        Self {
+            seller: "https://api-seller.ozon.ru".into(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct OzonSellerClient {
     client_id: String,
     api_key: String,
+    /// Базовые URL хостов API.
+    urls: OzonApiUrls,
+    /// Запись и воспроизведение ответов API.
+    cassette: Cassette,
+    /// Общий HTTP клиент (см. `HttpConfig::client`).
+    http: reqwest::Client,
+    /// Таймаут запроса.
+    timeout: Duration,
     last_request_time: AtomicU64,
     requests_per_sec: AtomicU32,
 }
 
 impl OzonSellerClient {
     pub fn new(client_id: String, api_key: String) -> Self {
+        Self::with_http(client_id, api_key, reqwest::Client::new(), DEFAULT_TIMEOUT)
+    }
+
+    /// Создаёт клиент, использующий общий HTTP клиент `http` с таймаутом запроса `timeout`.
+    pub fn with_http(
+        client_id: String,
+        api_key: String,
+        http: reqwest::Client,
+        timeout: Duration,
+    ) -> Self {
         Self {
             client_id,
             api_key,
+            urls: OzonApiUrls::default(),
+            cassette: Cassette::default(),
+            http,
+            timeout,
             last_request_time: AtomicU64::new(Self::now_millis()),
             requests_per_sec: AtomicU32::new(0),
         }
     }
 
-    /// Создает экземпляр клиента OzonSellerClient из переменных окружения.
-    ///
-    /// Переменные окружения:
-    /// - `OZON_SELLER_CLIENT_ID` — идентификатор клиента Ozon.
-    /// - `OZON_SELLER_API_KEY` — API ключ для доступа к API Ozon.
-    ///
-    /// # Паника
-    /// Метод вызовет `panic!`, если одна из переменных окружения не установлена.
-    pub fn from_env() -> Self {
-        let _ = dotenv::dotenv().ok();
-        let client_id = std::env::var("OZON_SELLER_CLIENT_ID")
-            .expect("Переменная окружения OZON_SELLER_CLIENT_ID не установлена");
-        let api_key = std::env::var("OZON_SELLER_API_KEY")
-            .expect("Переменная окружения OZON_SELLER_API_KEY не установлена");
-
-        Self::new(client_id, api_key)
+    /// Создает экземпляр клиента OzonSellerClient из конфигурации.
+    /// В режиме воспроизведения `cassette` учётные данные не требуются.
+    pub fn from_config(
+        cfg: &OzonSellerCredentials,
+        urls: &OzonApiUrls,
+        cassette: Cassette,
+        http: reqwest::Client,
+        timeout: Duration,
+    ) -> Result<Self> {
+        if cassette.mode() != CassetteMode::Replay {
+            cfg.validate()?;
+        }
+        Ok(
+            Self::with_http(cfg.client_id.clone(), cfg.api_key.clone(), http, timeout)
+                .with_urls(urls.clone())
+                .with_cassette(cassette),
+        )
+    }
+
+    /// Заменяет базовые URL хостов API.
+    pub fn with_urls(mut self, urls: OzonApiUrls) -> Self {
+        self.urls = OzonApiUrls {
+            seller: urls.seller.trim_end_matches('/').into(),
+        };
+        self
+    }
+
+    /// Включает запись или воспроизведение ответов API (см. `Cassette`).
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = cassette;
+        self
     }
 
     #[inline]
@@ -106,26 +163,27 @@ impl OzonSellerClient {
     async fn call_api<T: DeserializeOwned + 'static>(
         &self,
         method: Method,
-        url: &str,
+        path: &str,
         payload: Option<Vec<u8>>,
     ) -> Result<T> {
         self.throttle().await;
 
+        let url = format!("{}{path}", self.urls.seller);
+
         let mut headers = HeaderMap::new();
 
         headers.insert("Client-Id", self.client_id.parse().unwrap());
         headers.insert("Api-Key", self.api_key.parse().unwrap());
 
-        let mut reqwest_builder = reqwest::Client::default()
-            .request(method, url)
-            .timeout(DEFAULT_TIMEOUT);
+        let mut reqwest_builder = self.http.request(method, &url).timeout(self.timeout);
 
         if let Some(body) = payload {
             headers.insert("Content-Type", "application/json".parse().unwrap());
             reqwest_builder = reqwest_builder.body(body);
         }
 
-        let response = reqwest_builder.headers(headers).send().await?;
+        let request = reqwest_builder.headers(headers).build()?;
+        let response = self.cassette.execute(&self.http, request).await?;
 
         match response.error_for_status_ref() {
             Ok(_) => {
@@ -159,9 +217,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v3/product/list";
+        const PATH: &str = "/v3/product/list";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Получить информацию о товарах по идентификаторам
@@ -176,9 +234,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v3/product/info/list";
+        const PATH: &str = "/v3/product/info/list";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Получить описание товара
@@ -191,9 +249,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/product/info/description";
+        const PATH: &str = "/v1/product/info/description";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Получить описание характеристик товара
@@ -210,9 +268,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v4/product/info/attributes";
+        const PATH: &str = "/v4/product/info/attributes";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Дерево категорий и типов товаров
@@ -225,9 +283,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/description-category/tree";
+        const PATH: &str = "/v1/description-category/tree";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Список характеристик категории
@@ -244,9 +302,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/description-category/attribute";
+        const PATH: &str = "/v1/description-category/attribute";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Справочник значений характеристики
@@ -269,9 +327,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/description-category/attribute/values";
+        const PATH: &str = "/v1/description-category/attribute/values";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Получить список отзывов
@@ -290,9 +348,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/review/list";
+        const PATH: &str = "/v1/review/list";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Получить информацию об отзыве
@@ -302,9 +360,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/review/info";
+        const PATH: &str = "/v1/review/info";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Список комментариев на отзыв
@@ -321,9 +379,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/review/comment/list";
+        const PATH: &str = "/v1/review/comment/list";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Ответить на отзыв
@@ -342,18 +400,18 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/review/comment/create";
+        const PATH: &str = "/v1/review/comment/create";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Количество отзывов по статусам
     pub async fn get_review_count(&self) -> Result<models::ReviewsCountResponse> {
         let payload = "{}".as_bytes().to_vec();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/review/count";
+        const PATH: &str = "/v1/review/count";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Создать ответ на вопрос
@@ -370,9 +428,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/question/answer/create";
+        const PATH: &str = "/v1/question/answer/create";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Получить список ответов на вопрос
@@ -389,9 +447,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/question/answer/list";
+        const PATH: &str = "/v1/question/answer/list";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Получить информацию по вопросу
@@ -404,9 +462,9 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/question/info";
+        const PATH: &str = "/v1/question/info";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Получить список вопросов
@@ -421,18 +479,18 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/question/list";
+        const PATH: &str = "/v1/question/list";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Количество вопросов по статусам
     pub async fn get_question_count(&self) -> Result<models::QuestionsCountResponse> {
         let payload = "{}".as_bytes().to_vec();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/question/count";
+        const PATH: &str = "/v1/question/count";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Изменить статус вопросов
@@ -447,17 +505,17 @@ impl OzonSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/question/change-status";
+        const PATH: &str = "/v1/question/change-status";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 
     /// Получить информацию о текущих рейтингах продавца
     pub async fn seller_rating_summary(&self) -> Result<models::RatingSummaryResponse> {
         let payload = "{}".as_bytes().to_vec();
 
-        const URL: &str = "https://api-seller.ozon.ru/v1/rating/summary";
+        const PATH: &str = "/v1/rating/summary";
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, PATH, Some(payload)).await
     }
 }
@@ -25,13 +25,14 @@ pub enum Language {
 }
 
 #[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum SortDir {
     #[default]
     /// по возрастанию
-    ASC,
+    Asc,
 
     /// по убыванию
-    DESC,
+    Desc,
 }
 
 /// Универсальный фильтр.
@@ -161,3 +162,6 @@ pub const PRODUCT_LIST_MIN_LIMIT: usize = 1;
 
 /// Максимальное количество товаров в ответе
 pub const PRODUCT_LIST_MAX_LIMIT: usize = 1000;
+
+/// Максимальная длина ответа на вопрос или комментария к отзыву, символов
+pub const ANSWER_MAX_LEN: usize = 3000;
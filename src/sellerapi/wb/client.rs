@@ -1,7 +1,11 @@
 use super::models;
-use crate::error::Result;
+use crate::{
+    config::WbSellerCredentials,
+    error::Result,
+    sellerapi::{Cassette, CassetteMode},
+};
 use reqwest::{Method, header::HeaderMap};
-use serde::{Deserialize, de::DeserializeOwned};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::{Value, json};
 use std::{any::TypeId, fmt::Write, time::Duration};
 use thiserror::Error as ThisError;
@@ -39,36 +43,111 @@ macro_rules! unwrap_data_or_api_err {
     }};
 }
 
+/// Базовые URL хостов WB Seller API. Переопределяются, например, для локального
+/// тестового сервера.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WbApiUrls {
+    /// Контент: карточки товаров.
+    pub content: String,
+    /// Цены и скидки.
+    pub prices: String,
+    /// Статистика: остатки на складах.
+    pub statistics: String,
+    /// Вопросы и отзывы.
+    pub feedbacks: String,
+}
+
+impl Default for WbApiUrls {
+    fn default() -> Self {
+        Self {
+            content: "https://content-api.wildberries.ru".into(),
+            prices: "https://discounts-prices-api.wildberries.ru".into(),
+            statistics: "https://statistics-api.wildberries.ru".into(),
+            feedbacks: "https://feedbacks-api.wildberries.ru".into(),
+        }
+    }
+}
+
+impl WbApiUrls {
+    /// Все хосты API по одному адресу `base_url`.
+    pub fn all(base_url: &str) -> Self {
+        Self {
+            content: base_url.into(),
+            prices: base_url.into(),
+            statistics: base_url.into(),
+            feedbacks: base_url.into(),
+        }
+    }
+}
+
 /// Клиент для WB Seller API.
 #[derive(Debug)]
 pub struct WbSellerClient {
     token: String,
+    /// Базовые URL хостов API.
+    urls: WbApiUrls,
+    /// Запись и воспроизведение ответов API.
+    cassette: Cassette,
+    /// Общий HTTP клиент (см. `HttpConfig::client`).
+    http: reqwest::Client,
+    /// Таймаут запроса.
+    timeout: Duration,
     sem: Semaphore,
     // blocker: Arc<Mutex<()>>,
 }
 
 impl WbSellerClient {
     pub fn new(token: String) -> Self {
+        Self::with_http(token, reqwest::Client::new(), DEFAULT_TIMEOUT)
+    }
+
+    /// Создаёт клиент, использующий общий HTTP клиент `http` с таймаутом запроса `timeout`.
+    pub fn with_http(token: String, http: reqwest::Client, timeout: Duration) -> Self {
         Self {
             token,
+            urls: WbApiUrls::default(),
+            cassette: Cassette::default(),
+            http,
+            timeout,
             sem: Semaphore::const_new(MAX_RATE_LIMIT),
             // blocker: Arc::new(Mutex::new(())),
         }
     }
 
-    /// Создает экземпляр клиента WbSellerClient из переменных окружения.
-    ///
-    /// Переменные окружения:
-    /// - `WB_SELLER_API_TOKEN` — токен клиента Wb.
-    ///
-    /// # Паника
-    /// Метод вызовет `panic!`, если одна из переменных окружения не установлена.
-    pub fn from_env() -> Self {
-        let _ = dotenv::dotenv().ok();
-        let token = std::env::var("WB_SELLER_API_TOKEN")
-            .expect("Переменная окружения WB_SELLER_API_TOKEN не установлена");
-
-        Self::new(token)
+    /// Создает экземпляр клиента WbSellerClient из конфигурации.
+    /// В режиме воспроизведения `cassette` учётные данные не требуются.
+    pub fn from_config(
+        cfg: &WbSellerCredentials,
+        urls: &WbApiUrls,
+        cassette: Cassette,
+        http: reqwest::Client,
+        timeout: Duration,
+    ) -> Result<Self> {
+        if cassette.mode() != CassetteMode::Replay {
+            cfg.validate()?;
+        }
+        Ok(Self::with_http(cfg.token.clone(), http, timeout)
+            .with_urls(urls.clone())
+            .with_cassette(cassette))
+    }
+
+    /// Заменяет базовые URL хостов API.
+    pub fn with_urls(mut self, urls: WbApiUrls) -> Self {
+        let trim = |url: String| url.trim_end_matches('/').to_string();
+        self.urls = WbApiUrls {
+            content: trim(urls.content),
+            prices: trim(urls.prices),
+            statistics: trim(urls.statistics),
+            feedbacks: trim(urls.feedbacks),
+        };
+        self
+    }
+
+    /// Включает запись или воспроизведение ответов API (см. `Cassette`).
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = cassette;
+        self
     }
 
     #[inline]
@@ -86,16 +165,15 @@ impl WbSellerClient {
         let mut headers = HeaderMap::new();
         headers.insert("Authorization", self.token.parse().unwrap());
 
-        let mut reqwest_builder = reqwest::Client::default()
-            .request(method, url)
-            .timeout(DEFAULT_TIMEOUT);
+        let mut reqwest_builder = self.http.request(method, url).timeout(self.timeout);
 
         if let Some(body) = payload {
             headers.insert("Content-Type", "application/json".parse().unwrap());
             reqwest_builder = reqwest_builder.body(body);
         }
 
-        let response = reqwest_builder.headers(headers).send().await?;
+        let request = reqwest_builder.headers(headers).build()?;
+        let response = self.cassette.execute(&self.http, request).await?;
 
         match response.error_for_status_ref() {
             Ok(_) => {
@@ -145,16 +223,21 @@ impl WbSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://content-api.wildberries.ru/content/v2/get/cards/list";
+        let url = format!("{}/content/v2/get/cards/list", self.urls.content);
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, &url, Some(payload)).await
     }
 
     /// Внешний метод получения Rich контента. Не использует авторизации.
-    pub async fn get_product_rich_content(basket_urlpath: &str, version: i32) -> Result<String> {
+    pub async fn get_product_rich_content(
+        &self,
+        basket_urlpath: &str,
+        version: i32,
+    ) -> Result<String> {
         let url = format!("{}/info/ru/rich_v{}.json", basket_urlpath, version);
 
-        let responce = reqwest::get(&url).await?;
+        let request = self.http.get(&url).timeout(self.timeout).build()?;
+        let responce = self.cassette.execute(&self.http, request).await?;
 
         match responce.error_for_status() {
             Ok(res) => Ok(res.text().await?),
@@ -169,9 +252,9 @@ impl WbSellerClient {
         offset: Option<u32>,
         filter_nmid: Option<i64>,
     ) -> Result<models::GoodsListResponse> {
-        const URL: &str = "https://discounts-prices-api.wildberries.ru/api/v2/list/goods/filter";
+        let url = format!("{}/api/v2/list/goods/filter", self.urls.prices);
 
-        let mut url_with_query = format!("{}?limit={}", URL, limit);
+        let mut url_with_query = format!("{}?limit={}", &url, limit);
 
         if let Some(val) = offset {
             let _ = write!(&mut url_with_query, "&offset={}", val);
@@ -183,6 +266,16 @@ impl WbSellerClient {
         self.call_api(Method::GET, &url_with_query, None).await
     }
 
+    /// [Остатки на складах WB](https://dev.wildberries.ru/openapi/reports#tag/Osnovnye-otchyoty/paths/~1api~1v1~1supplier~1stocks/get)
+    /// Возвращает остатки, изменившиеся после `date_from` (`YYYY-MM-DD`);
+    /// для получения всех остатков указывается самая ранняя дата.
+    pub async fn get_supplier_stocks(&self, date_from: &str) -> Result<Vec<models::StockItem>> {
+        let url = format!("{}/api/v1/supplier/stocks", self.urls.statistics);
+
+        self.call_api(Method::GET, &format!("{url}?dateFrom={date_from}"), None)
+            .await
+    }
+
     /// [Работа с вопросами](https://dev.wildberries.ru/openapi/user-communication/#tag/Voprosy/paths/~1api~1v1~1questions/patch)
     /// Обновляет состояние вопроса:
     /// - ответить или отредактировать ответ,
@@ -209,10 +302,10 @@ impl WbSellerClient {
 
         let payload = serde_json::to_vec(&value).unwrap();
 
-        const URL: &str = "https://feedbacks-api.wildberries.ru/api/v1/questions";
+        let url = format!("{}/api/v1/questions", self.urls.feedbacks);
 
         match self
-            .call_api::<models::UpdateQuestionResponse>(Method::PATCH, URL, Some(payload))
+            .call_api::<models::UpdateQuestionResponse>(Method::PATCH, &url, Some(payload))
             .await
         {
             Ok(res) => unwrap_data_or_api_err!(res),
@@ -226,9 +319,9 @@ impl WbSellerClient {
         &self,
         filter: &models::params::QuestionsAndReviewsFilter,
     ) -> Result<models::QuestionListData> {
-        const URL: &str = "https://feedbacks-api.wildberries.ru/api/v1/questions";
+        let url = format!("{}/api/v1/questions", self.urls.feedbacks);
 
-        let url_with_query = format!("{}?{}", URL, filter.as_query_params());
+        let url_with_query = format!("{}?{}", &url, filter.as_query_params());
 
         match self
             .call_api::<models::QuestionListResponse>(Method::GET, &url_with_query, None)
@@ -247,7 +340,7 @@ impl WbSellerClient {
         date_from: Option<u64>,
         date_to: Option<u64>,
     ) -> Result<u32> {
-        const URL: &str = "https://feedbacks-api.wildberries.ru/api/v1/questions/count";
+        let url = format!("{}/api/v1/questions/count", self.urls.feedbacks);
 
         let mut query = String::new();
 
@@ -262,15 +355,15 @@ impl WbSellerClient {
         }
 
         let url_with_query = if !query.is_empty() {
-            Some(format!("{}?{}", URL, query.trim_end_matches("&")))
+            Some(format!("{}?{}", &url, query.trim_end_matches("&")))
         } else {
             None
         };
 
-        let full_url = url_with_query.as_ref().map_or(URL, |v| v);
+        let full_url = url_with_query.as_ref().map_or(url.as_str(), |v| v);
 
         match self
-            .call_api::<models::ReviewsCountResponse>(Method::GET, &full_url, None)
+            .call_api::<models::ReviewsCountResponse>(Method::GET, full_url, None)
             .await
         {
             Ok(res) => unwrap_data_or_api_err!(res),
@@ -280,10 +373,10 @@ impl WbSellerClient {
 
     /// [Непросмотренные отзывы и вопросы](https://feedbacks-api.wildberries.ru/api/v1/new-feedbacks-questions)
     pub async fn get_new_feedbacks(&self) -> Result<models::NewFeedbacksQuestionsData> {
-        const URL: &str = "https://feedbacks-api.wildberries.ru/api/v1/new-feedbacks-questions";
+        let url = format!("{}/api/v1/new-feedbacks-questions", self.urls.feedbacks);
 
         match self
-            .call_api::<models::NewFeedbacksQuestionsResponse>(Method::GET, URL, None)
+            .call_api::<models::NewFeedbacksQuestionsResponse>(Method::GET, &url, None)
             .await
         {
             Ok(res) => unwrap_data_or_api_err!(res),
@@ -299,9 +392,9 @@ impl WbSellerClient {
         }))
         .unwrap();
 
-        const URL: &str = "https://feedbacks-api.wildberries.ru/api/v1/feedbacks/answer";
+        let url = format!("{}/api/v1/feedbacks/answer", self.urls.feedbacks);
 
-        self.call_api(Method::POST, URL, Some(payload)).await
+        self.call_api(Method::POST, &url, Some(payload)).await
     }
 
     /// [Список отзывов](https://dev.wildberries.ru/openapi/user-communication/#tag/Otzyvy/paths/~1api~1v1~1feedbacks/get)
@@ -310,9 +403,9 @@ impl WbSellerClient {
         &self,
         filter: &models::params::QuestionsAndReviewsFilter,
     ) -> Result<models::ReviewListData> {
-        const URL: &str = "https://feedbacks-api.wildberries.ru/api/v1/feedbacks";
+        let url = format!("{}/api/v1/feedbacks", self.urls.feedbacks);
 
-        let url_with_query = format!("{}?{}", URL, filter.as_query_params());
+        let url_with_query = format!("{}?{}", &url, filter.as_query_params());
 
         match self
             .call_api::<models::ReviewListResponse>(Method::GET, &url_with_query, None)
@@ -331,7 +424,7 @@ impl WbSellerClient {
         date_from: Option<u64>,
         date_to: Option<u64>,
     ) -> Result<u32> {
-        const URL: &str = "https://feedbacks-api.wildberries.ru/api/v1/feedbacks/count";
+        let url = format!("{}/api/v1/feedbacks/count", self.urls.feedbacks);
 
         let mut query = String::new();
 
@@ -346,15 +439,15 @@ impl WbSellerClient {
         }
 
         let url_with_query = if !query.is_empty() {
-            Some(format!("{}?{}", URL, query.trim_end_matches("&")))
+            Some(format!("{}?{}", &url, query.trim_end_matches("&")))
         } else {
             None
         };
 
-        let full_url = url_with_query.as_ref().map_or(URL, |v| v);
+        let full_url = url_with_query.as_ref().map_or(url.as_str(), |v| v);
 
         match self
-            .call_api::<models::ReviewsCountResponse>(Method::GET, &full_url, None)
+            .call_api::<models::ReviewsCountResponse>(Method::GET, full_url, None)
             .await
         {
             Ok(res) => unwrap_data_or_api_err!(res),
@@ -364,10 +457,10 @@ impl WbSellerClient {
 
     /// [Необработанные отзывы](https://dev.wildberries.ru/openapi/user-communication/#tag/Otzyvy/paths/~1api~1v1~1feedbacks~1count-unanswered/get)
     pub async fn get_review_count_unanswered(&self) -> Result<models::ReviewsCountUnansweredData> {
-        const URL: &str = "https://feedbacks-api.wildberries.ru/api/v1/feedbacks/count-unanswered";
+        let url = format!("{}/api/v1/feedbacks/count-unanswered", self.urls.feedbacks);
 
         match self
-            .call_api::<models::ReviewsCountUnansweredResponse>(Method::GET, URL, None)
+            .call_api::<models::ReviewsCountUnansweredResponse>(Method::GET, &url, None)
             .await
         {
             Ok(res) => unwrap_data_or_api_err!(res),
@@ -33,7 +33,7 @@ pub struct CardListCursor {
     /// Дата и время изменения
     pub updated_at: Option<String>,
 
-    #[serde(skip_serializing_if = "Option::is_none", rename = "imtID")]
+    #[serde(skip_serializing_if = "Option::is_none", rename = "nmID")]
     /// Артикул WB, с которого надо запрашивать следующий список карточек товаров
     pub nm_id: Option<i64>,
 }
@@ -135,3 +135,12 @@ pub const REVIEW_MAX_LIMIT: usize = 5000;
 
 /// Максимальное количество вопросов в ответе
 pub const QUESTION_MAX_LIMIT: usize = 10000;
+
+/// Максимальное количество пропускаемых вопросов (`skip`)
+pub const QUESTION_MAX_SKIP: usize = 10_000;
+
+/// Максимальное количество пропускаемых отзывов (`skip`)
+pub const REVIEW_MAX_SKIP: usize = 199_990;
+
+/// Максимальная длина ответа на вопрос или отзыв, символов
+pub const ANSWER_MAX_LEN: usize = 5000;
@@ -264,3 +264,27 @@ pub struct SizePrice {
     #[serde(rename = "techSizeName")]
     pub tech_size_name: String,
 }
+
+/// Остаток товара на складе WB (`/api/v1/supplier/stocks`)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StockItem {
+    /// Название склада
+    #[serde(rename = "warehouseName", default)]
+    pub warehouse_name: String,
+
+    /// Артикул WB
+    #[serde(rename = "nmId")]
+    pub nm_id: i64,
+
+    /// Размер товара
+    #[serde(rename = "techSize", default)]
+    pub tech_size: String,
+
+    /// Количество, доступное для продажи
+    #[serde(default)]
+    pub quantity: i64,
+
+    /// Полное количество, включая товары в пути
+    #[serde(rename = "quantityFull", default)]
+    pub quantity_full: i64,
+}
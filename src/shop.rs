@@ -0,0 +1,130 @@
+use crate::{
+    config::{Config, ShopConfig},
+    controller::feedback::AutoApproveRule,
+    error::{Error, Result},
+    sellerapi::{OzonSellerClient, SellerClient, WbSellerClient},
+};
+use std::sync::Arc;
+
+/// Магазин — кабинет продавца с собственным клиентом seller API,
+/// шаблонами, моделью и политикой обработки.
+#[derive(Clone)]
+pub struct Shop {
+    /// Идентификатор магазина, префикс ключей записей в базе данных.
+    pub id: String,
+    pub scli: SellerClient,
+    /// Модель AI провайдера.
+    pub model: String,
+    /// Путь к шаблону промпта для ответа на вопрос.
+    pub question_template: String,
+    /// Путь к шаблону промпта для ответа на отзыв.
+    pub review_template: String,
+    /// Путь к Lua скрипту политики обработки.
+    pub policy: Option<String>,
+    /// Правила автоматического одобрения черновиков.
+    pub auto_approve: Vec<AutoApproveRule>,
+}
+
+impl Shop {
+    /// Создаёт магазин из `shop`, подставляя незаданные параметры из общей конфигурации `cfg`.
+    /// `http` — общий HTTP клиент (см. `HttpConfig::client`).
+    pub fn from_config(shop: &ShopConfig, cfg: &Config, http: &reqwest::Client) -> Result<Self> {
+        let timeout = cfg.http.timeout();
+        let scli = match shop.place.as_str() {
+            "oz" => SellerClient::Ozon(Arc::new(OzonSellerClient::from_config(
+                &shop.ozon_seller_credentials,
+                &cfg.ozon_api,
+                cfg.cassette.cassette(shop),
+                http.clone(),
+                timeout,
+            )?)),
+            "wb" => SellerClient::Wb(Arc::new(WbSellerClient::from_config(
+                &shop.wb_seller_credentials,
+                &cfg.wb_api,
+                cfg.cassette.cassette(shop),
+                http.clone(),
+                timeout,
+            )?)),
+            place => {
+                return Err(Error::Config(format!(
+                    "shop {}: unknown place \"{place}\"",
+                    shop.id
+                )));
+            }
+        };
+
+        Ok(Self {
+            id: shop.id.clone(),
+            scli,
+            model: shop
+                .model
+                .clone()
+                .unwrap_or_else(|| cfg.llm_config.model.clone()),
+            question_template: shop
+                .question_template
+                .clone()
+                .unwrap_or_else(|| cfg.templates.question.clone()),
+            review_template: shop
+                .review_template
+                .clone()
+                .unwrap_or_else(|| cfg.templates.review.clone()),
+            policy: shop.policy.clone().or_else(|| cfg.policy.clone()),
+            auto_approve: shop
+                .auto_approve
+                .clone()
+                .unwrap_or_else(|| cfg.auto_approve.clone()),
+        })
+    }
+
+    /// Ключ записи магазина в базе данных: `"{shop_id}/{id}"`.
+    #[inline]
+    pub fn key(&self, id: &str) -> String {
+        format!("{}/{}", self.id, id)
+    }
+}
+
+/// Реестр магазинов из конфигурации.
+pub struct ShopRegistry {
+    shops: Vec<Shop>,
+}
+
+impl ShopRegistry {
+    /// Создаёт магазины конфигурации с общим HTTP клиентом `http`.
+    pub fn from_config(cfg: &Config, http: &reqwest::Client) -> Result<Self> {
+        let shops = cfg
+            .shops()
+            .iter()
+            .map(|shop| Shop::from_config(shop, cfg, http))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { shops })
+    }
+
+    #[inline]
+    pub fn get(&self, id: &str) -> Option<&Shop> {
+        self.shops.iter().find(|s| s.id == id)
+    }
+
+    /// Возвращает магазин `id`, а если он не задан — единственный магазин реестра.
+    pub fn select(&self, id: Option<&str>) -> Result<&Shop> {
+        match id {
+            Some(id) => self
+                .get(id)
+                .ok_or_else(|| Error::Config(format!("unknown shop \"{id}\""))),
+            None if self.shops.len() == 1 => Ok(&self.shops[0]),
+            None => Err(Error::Config(
+                "several shops are configured, select one with --shop".into(),
+            )),
+        }
+    }
+
+    #[inline]
+    pub fn iter(&self) -> std::slice::Iter<'_, Shop> {
+        self.shops.iter()
+    }
+
+    #[inline]
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Shop> {
+        self.shops.iter_mut()
+    }
+}
@@ -1,6 +1,6 @@
 use hyper::service::service_fn;
 use hyper_util::rt::TokioIo;
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 use tokio::net::{TcpListener, ToSocketAddrs};
 
 mod router;
@@ -10,6 +10,7 @@ pub use state::*;
 
 pub async fn run<A: ToSocketAddrs>(
     addr: A,
+    state: Arc<AppState>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let listener = TcpListener::bind(addr).await?;
 
@@ -19,10 +20,13 @@ pub async fn run<A: ToSocketAddrs>(
         let (stream, _) = listener.accept().await?;
 
         let io = TokioIo::new(stream);
+        let state = state.clone();
 
         tokio::task::spawn(async move {
-            let conn = hyper::server::conn::http1::Builder::new()
-                .serve_connection(io, service_fn(router::handler));
+            let conn = hyper::server::conn::http1::Builder::new().serve_connection(
+                io,
+                service_fn(move |req| router::handler(req, state.clone())),
+            );
             tokio::pin!(conn);
 
             for timeout in CONNECTION_TIMEOUTS {
@@ -1,14 +1,18 @@
-use crate::error::Result;
+use super::{AppState, DEFAULT_TEMPLATES_DIR};
+use crate::{
+    db::{self, FeedbackStatus},
+    error::Result,
+};
 use http_body_util::{BodyExt, Empty, Full, combinators::BoxBody};
 use hyper::{
     Method, Request, Response, StatusCode,
     body::{self, Bytes},
 };
-use std::path::Path;
+use std::{path::Path, sync::Arc};
 
 type ResponseT = Response<BoxBody<Bytes, hyper::Error>>;
 
-pub async fn handler(req: Request<body::Incoming>) -> Result<ResponseT> {
+pub async fn handler(req: Request<body::Incoming>, state: Arc<AppState>) -> Result<ResponseT> {
     let uri = req.uri();
 
     match (req.method(), uri.path()) {
@@ -16,7 +20,7 @@ pub async fn handler(req: Request<body::Incoming>) -> Result<ResponseT> {
             .header("Content-Type", TEXT_HTML_UTF_8)
             .body(empty())
             .unwrap()),
-        (&Method::GET, "/api/templates") => match template_list().await {
+        (&Method::GET, "/api/templates") => match template_list(&state.templates_dir).await {
             Ok(template_list) => Ok(Response::builder()
                 .header("Content-Type", APPLICATION_JSON)
                 .body(full(serde_json::to_vec(&template_list).unwrap()))
@@ -28,7 +32,7 @@ pub async fn handler(req: Request<body::Incoming>) -> Result<ResponseT> {
         },
         (&Method::GET, p) if p.starts_with("/api/read/template/") => {
             let path =
-                Path::new(TEMPLATES_PATH_DIR).join(p.trim_start_matches("/api/read/template/"));
+                Path::new(&state.templates_dir).join(p.trim_start_matches("/api/read/template/"));
             match tokio::fs::read(&path).await {
                 Ok(template) => Ok(Response::builder()
                     .header("Content-Type", TEXT_PLAIN_UTF_8)
@@ -42,9 +46,11 @@ pub async fn handler(req: Request<body::Incoming>) -> Result<ResponseT> {
         }
         (&Method::POST, p) if p.starts_with("/api/write/template/") => {
             let path =
-                Path::new(TEMPLATES_PATH_DIR).join(p.trim_start_matches("/api/write/template/"));
-            let content = req.into_body().collect().await.unwrap().to_bytes();
-            match tokio::fs::write(&path, content).await {
+                Path::new(&state.templates_dir).join(p.trim_start_matches("/api/write/template/"));
+            let Ok(content) = req.into_body().collect().await else {
+                return Ok(bad_request());
+            };
+            match tokio::fs::write(&path, content.to_bytes()).await {
                 Ok(_) => Ok(Response::builder().body(empty()).unwrap()),
                 Err(_) => Ok(Response::builder()
                     .status(StatusCode::NOT_FOUND)
@@ -52,6 +58,59 @@ pub async fn handler(req: Request<body::Incoming>) -> Result<ResponseT> {
                     .unwrap()),
             }
         }
+        (&Method::GET, "/api/drafts") => {
+            let status = uri
+                .query()
+                .and_then(|q| q.split('&').find_map(|kv| kv.strip_prefix("status=")))
+                .map_or(Some(FeedbackStatus::Generated), FeedbackStatus::parse);
+
+            let Some(status) = status else {
+                return Ok(bad_request());
+            };
+
+            match db::select_feedback_by_status(status, DRAFTS_LIST_LIMIT).await {
+                Ok(rows) => Ok(Response::builder()
+                    .header("Content-Type", APPLICATION_JSON)
+                    .body(full(serde_json::to_vec(&rows).unwrap()))
+                    .unwrap()),
+                Err(_) => Ok(Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(empty())
+                    .unwrap()),
+            }
+        }
+        (&Method::POST, p) if p.starts_with("/api/drafts/") => {
+            let Some((id, action)) = p.trim_start_matches("/api/drafts/").rsplit_once('/') else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+            let (id, action) = (id.to_string(), action.to_string());
+
+            let Ok(content) = req.into_body().collect().await else {
+                return Ok(bad_request());
+            };
+            let text = String::from_utf8_lossy(&content.to_bytes())
+                .trim()
+                .to_string();
+
+            match draft_action(&id, &action, &text).await {
+                Ok(Some(true)) => Ok(Response::builder().body(empty()).unwrap()),
+                Ok(Some(false)) => Ok(Response::builder()
+                    .status(StatusCode::CONFLICT)
+                    .body(empty())
+                    .unwrap()),
+                Ok(None) => Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap()),
+                Err(_) => Ok(Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(empty())
+                    .unwrap()),
+            }
+        }
         _ => Ok(Response::builder()
             .status(StatusCode::NOT_FOUND)
             .header("Content-Type", TEXT_HTML_UTF_8)
@@ -60,6 +119,13 @@ pub async fn handler(req: Request<body::Incoming>) -> Result<ResponseT> {
     }
 }
 
+fn bad_request() -> ResponseT {
+    Response::builder()
+        .status(StatusCode::BAD_REQUEST)
+        .body(empty())
+        .unwrap()
+}
+
 fn empty() -> BoxBody<Bytes, hyper::Error> {
     Empty::<Bytes>::new()
         .map_err(|never| match never {})
@@ -72,17 +138,17 @@ fn full<T: Into<Bytes>>(chunk: T) -> BoxBody<Bytes, hyper::Error> {
         .boxed()
 }
 
-async fn template_list() -> Result<Vec<String>> {
+async fn template_list(dir: &str) -> Result<Vec<String>> {
     let mut res = Vec::new();
 
-    if !Path::new(TEMPLATES_PATH_DIR).exists() {
-        tokio::fs::create_dir(TEMPLATES_PATH_DIR).await?;
+    if !Path::new(dir).exists() {
+        tokio::fs::create_dir(dir).await?;
         return Ok(res);
     }
 
-    let mut read_dir = tokio::fs::read_dir(TEMPLATES_PATH_DIR).await?;
+    let mut read_dir = tokio::fs::read_dir(dir).await?;
     while let Some(entry) = read_dir.next_entry().await? {
-        if let Some(path) = entry.path().into_iter().last().and_then(|v| v.to_str()) {
+        if let Some(path) = entry.path().file_name().and_then(|v| v.to_str()) {
             res.push(path.to_owned());
         }
     }
@@ -90,7 +156,29 @@ async fn template_list() -> Result<Vec<String>> {
     Ok(res)
 }
 
-const TEMPLATES_PATH_DIR: &str = "templates";
+/// Действие над черновиком ответа:
+/// - `approve` — одобрить (тело запроса, если не пустое, заменяет текст ответа),
+/// - `reject` — отклонить,
+/// - `edit` — заменить текст ответа телом запроса,
+/// - `retry` — сбросить счётчик попыток записи в статусе `received` или `approved`,
+///   например, после исчерпания попыток публикации одобренного ответа.
+///
+/// Остальные действия применимы к записям в статусах `generated` и `escalated`.
+/// Возвращает `None` для неизвестного действия и `Some(false)`,
+/// если запись не найдена или находится в неподходящем статусе.
+async fn draft_action(id: &str, action: &str, text: &str) -> Result<Option<bool>> {
+    let done = match action {
+        "approve" => db::approve_feedback(id, Some(text).filter(|v| !v.is_empty())).await?,
+        "reject" => db::reject_feedback(id).await?,
+        "edit" => !text.is_empty() && db::update_feedback_draft(id, text).await?,
+        "retry" => db::retry_feedback(id).await?,
+        _ => return Ok(None),
+    };
+
+    Ok(Some(done))
+}
+
+const DRAFTS_LIST_LIMIT: u32 = 100;
 
 const TEXT_HTML_UTF_8: &[u8] = b"text/html; charset=utf-8";
 
@@ -114,5 +202,5 @@ const NOT_FOUND_PAGE: &str = r#"<!DOCTYPE html>
 
 #[tokio::test]
 async fn templates_list_test() {
-    println!("{:?}", template_list().await.unwrap());
+    println!("{:?}", template_list(DEFAULT_TEMPLATES_DIR).await.unwrap());
 }
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Mutex, time::Instant};
 use tera::Context;
 
+pub const DEFAULT_TEMPLATES_DIR: &str = "templates";
+
 // #[derive(Debug, Default, Serialize, Deserialize)]
 // pub struct Config {
 //     pub seller_client: OzonSellerClient,
@@ -12,4 +14,21 @@ use tera::Context;
 pub struct AppState {
     // pub cfg: Mutex<Config>,
     pub ctx_cache: Mutex<HashMap<String, (Context, Instant)>>,
+    /// Каталог шаблонов промптов.
+    pub templates_dir: String,
+}
+
+impl AppState {
+    pub fn new(templates_dir: &str) -> Self {
+        Self {
+            ctx_cache: Mutex::new(HashMap::new()),
+            templates_dir: templates_dir.to_string(),
+        }
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new(DEFAULT_TEMPLATES_DIR)
+    }
 }